@@ -1,34 +1,38 @@
-use pheasant_core::tls::{make_cert, tls_conn};
-use rustls::Stream;
-use std::io::{Read, Write};
-use std::net::{TcpListener, TcpStream};
-
-fn main() -> Result<(), std::io::Error> {
-    make()
-}
-
-fn make() -> Result<(), std::io::Error> {
-    // make_cert(&["localhost".into()]);
-    conn()
+use pheasant_core::tls::self_signed_config;
+use pheasant_core::{Method, Protocol, Response, Server, Service, TlsInfo};
+use pheasant_uri::Route;
+
+#[tokio::main]
+async fn main() {
+    let mut server = Server::new([127, 0, 0, 1], 7878, 64).unwrap();
+    server
+        .tls(self_signed_config(&["localhost".into()]).unwrap())
+        .service(|| {
+            Service::new(
+                Method::Get,
+                Route::macro_checked("/"),
+                None,
+                None,
+                None,
+                hello,
+            )
+        });
+
+    server.serve_with_signals().await;
 }
 
-fn conn() -> Result<(), std::io::Error> {
-    let listener = TcpListener::bind("localhost:7878")?;
-    let (mut stream, _) = listener.accept()?;
-
-    let mut serverconn = tls_conn(&["localhost".into()]).unwrap();
-    let mut tlss = Stream::new(&mut serverconn, &mut stream);
-    println!("2");
-
-    tlss.write_all(b"hello from tls secured server connection")?;
-    tlss.flush()?;
-    println!("3");
-
-    let mut buf = [0; 2048];
-    let len = tlss.read(&mut buf)?;
-    println!("4");
-
-    println!("{:?}", str::from_utf8(&buf).unwrap());
-
-    Ok(())
+async fn hello(tls: Option<TlsInfo>, proto: Protocol) -> Response {
+    let mut resp = Response::with_proto(proto);
+    let tls = tls.unwrap();
+    resp.update_body(
+        format!(
+            "hello from tls secured server connection\n{:?} {:?} {:?}\n",
+            tls.version(),
+            tls.cipher(),
+            tls.alpn(),
+        )
+        .into_bytes(),
+    );
+
+    resp
 }
@@ -0,0 +1,107 @@
+//! issues development tls certificates signed by a local certificate authority
+//!
+//! the ca is generated in the output directory on the first run and reused after that,
+//! clients only have to trust its `ca.pem` once
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use pheasant_core::tls::{DevCa, KeyType, TlsError};
+
+const USAGE: &str = "\
+usage: dev-ca [options] <san>...
+
+issues a certificate for the subject alternative names (dns names or ip addresses)
+signed by the dev ca of the output directory, the ca is created if it doesn't exist
+
+options:
+    --dir <path>     output directory [default: tls]
+    --name <name>    certificate file name, written to <name>.pem and <name>.key [default: first san]
+    --days <n>       certificate validity in days [default: 90]
+    --ca-days <n>    validity in days of a newly created ca [default: 3650]
+    --key <type>     key type, one of p256, p384, ed25519 [default: p256]
+    -h, --help       print this message";
+
+struct Args {
+    dir: PathBuf,
+    name: Option<String>,
+    days: u32,
+    ca_days: u32,
+    key: KeyType,
+    sans: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        dir: PathBuf::from("tls"),
+        name: None,
+        days: 90,
+        ca_days: 3650,
+        key: KeyType::default(),
+        sans: vec![],
+    };
+
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        let mut value = || argv.next().ok_or(format!("{} expects a value", arg));
+        match arg.as_str() {
+            "--dir" => args.dir = value()?.into(),
+            "--name" => args.name = Some(value()?),
+            "--days" => args.days = value()?.parse().map_err(|e| format!("--days: {}", e))?,
+            "--ca-days" => {
+                args.ca_days = value()?.parse().map_err(|e| format!("--ca-days: {}", e))?
+            }
+            "--key" => args.key = value()?.parse().map_err(|e: TlsError| e.to_string())?,
+            "-h" | "--help" => return Err(USAGE.into()),
+            flag if flag.starts_with('-') => return Err(format!("unknown option {}", flag)),
+            _ => args.sans.push(arg),
+        }
+    }
+
+    if args.sans.is_empty() {
+        return Err(USAGE.into());
+    }
+
+    Ok(args)
+}
+
+fn run(args: Args) -> Result<(), TlsError> {
+    let ca = if args.dir.join("ca.pem").exists() {
+        DevCa::load(&args.dir)?
+    } else {
+        let ca = DevCa::generate("pheasant dev ca", args.key, args.ca_days)?;
+        ca.write(&args.dir)?;
+        println!("created ca {}", args.dir.join("ca.pem").display());
+
+        ca
+    };
+
+    let name = args.name.as_ref().unwrap_or(&args.sans[0]);
+    ca.issue(&args.sans, args.key, args.days)?
+        .write(&args.dir, name)?;
+    println!(
+        "issued {} for {}",
+        args.dir.join(format!("{}.pem", name)).display(),
+        args.sans.join(", ")
+    );
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("dev-ca: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
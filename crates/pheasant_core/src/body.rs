@@ -0,0 +1,157 @@
+use std::fmt;
+use std::pin::Pin;
+
+use futures_core::Stream;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+// how much of an AsyncRead body source is read per chunk
+const READ_CHUNK: usize = 8 * 1024;
+
+/// http response body
+#[derive(Debug)]
+pub enum Body {
+    /// the whole body, already in memory
+    Bytes(Vec<u8>),
+    /// a body that is generated while it is being sent
+    Stream(BodyStream),
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Bytes(bytes)
+    }
+}
+
+impl From<BodyStream> for Body {
+    fn from(stream: BodyStream) -> Self {
+        Self::Stream(stream)
+    }
+}
+
+/// a response body that is written to the client chunk by chunk
+/// as its source yields data
+///
+/// when the length of the body is not known beforehand,
+/// it gets sent with `Transfer-Encoding: chunked`
+///
+/// ```no_run
+/// # use pheasant_core::{BodyStream, Response};
+/// # async fn video(mut resp: Response) -> std::io::Result<Response> {
+/// let file = tokio::fs::File::open("assets/video.mp4").await?;
+/// let len = file.metadata().await?.len();
+/// resp.update_body(BodyStream::from_reader(file).with_len(len));
+/// # Ok(resp)
+/// # }
+/// ```
+pub struct BodyStream {
+    source: Source,
+    len: Option<u64>,
+}
+
+enum Source {
+    Stream(Pin<Box<dyn Stream<Item = Vec<u8>> + Send>>),
+    Reader(Pin<Box<dyn AsyncRead + Send>>),
+}
+
+impl fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = match self.source {
+            Source::Stream(_) => "Stream",
+            Source::Reader(_) => "Reader",
+        };
+
+        f.debug_struct("BodyStream")
+            .field("source", &source)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl BodyStream {
+    /// makes a body out of a stream of byte chunks
+    pub fn from_stream<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Vec<u8>> + Send + 'static,
+    {
+        Self {
+            source: Source::Stream(Box::pin(stream)),
+            len: None,
+        }
+    }
+
+    /// makes a body out of an async reader, e.g., a `tokio::fs::File`
+    pub fn from_reader<R>(reader: R) -> Self
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        Self {
+            source: Source::Reader(Box::pin(reader)),
+            len: None,
+        }
+    }
+
+    /// sets the length of the body,
+    /// which is then sent as the `Content-Length` instead of chunking the body
+    ///
+    /// the source has to yield exactly `len` bytes
+    pub fn with_len(mut self, len: u64) -> Self {
+        self.len = Some(len);
+
+        self
+    }
+
+    /// returns the length of the body if it is known,
+    /// it is sent as the `Content-Length` of the response
+    pub fn content_length(&self) -> Option<u64> {
+        self.len
+    }
+
+    /// checks if the body is going to be sent in chunks
+    pub fn is_chunked(&self) -> bool {
+        self.len.is_none()
+    }
+
+    // yields the next chunk of the body, None once the source is exhausted
+    pub(crate) async fn next_chunk(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        match self.source {
+            Source::Stream(ref mut stream) => {
+                Ok(std::future::poll_fn(|cx| stream.as_mut().poll_next(cx)).await)
+            }
+            Source::Reader(ref mut reader) => {
+                let mut chunk = vec![0; READ_CHUNK];
+                let n = reader.read(&mut chunk).await?;
+                chunk.truncate(n);
+
+                Ok((n > 0).then_some(chunk))
+            }
+        }
+    }
+
+    // writes the whole body to the client,
+    // each chunk is flushed as soon as it is written
+    pub(crate) async fn write_to<W>(mut self, w: &mut W, chunked: bool) -> std::io::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        while let Some(chunk) = self.next_chunk().await? {
+            if chunk.is_empty() {
+                continue;
+            }
+            if chunked {
+                w.write_all(format!("{:x}\r\n", chunk.len()).as_bytes())
+                    .await?;
+                w.write_all(&chunk).await?;
+                w.write_all(b"\r\n").await?;
+            } else {
+                w.write_all(&chunk).await?;
+            }
+            w.flush().await?;
+        }
+
+        if chunked {
+            w.write_all(b"0\r\n\r\n").await?;
+        }
+
+        w.flush().await
+    }
+}
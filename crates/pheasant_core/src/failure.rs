@@ -6,6 +6,8 @@ pub struct Failure {
     mime: Option<Mime>,
     status: u16,
     fail: BoxFun,
+    // the type name of the handler function, to tell failures apart in registration errors
+    name: &'static str,
 }
 
 unsafe impl Send for Failure {}
@@ -27,6 +29,7 @@ impl Failure {
         Self {
             status,
             mime,
+            name: std::any::type_name::<F>(),
             fail: Box::new(move || Box::pin(fun())),
         }
     }
@@ -43,6 +46,11 @@ impl Failure {
         self.status.try_into().unwrap()
     }
 
+    /// returns the name of the handler function, e.g., "app::not_found"
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
     pub fn fail(&self) -> &BoxFun {
         &self.fail
     }
@@ -49,12 +49,23 @@ pub trait HeaderMap {
     }
 }
 
+// header names are case insensitive, the exact name is tried before the others
+pub(crate) fn find<'a>(map: &'a HashMap<String, String>, key: &str) -> Option<&'a String> {
+    map.get(key).or_else(|| {
+        map.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value)
+    })
+}
+
 impl HeaderMap for HashMap<String, String> {
     fn header<H: Header>(&self, key: &str) -> Option<H> {
-        self.get(key).map(|s| <H as Header>::from_str(s))
+        find(self, key).map(|s| <H as Header>::from_str(s))
     }
 
     fn set_header<H: Header>(&mut self, key: &str, h: H) -> &mut Self {
+        // replaces the header even if it was set under another case
+        self.retain(|name, _| name == key || !name.eq_ignore_ascii_case(key));
         self.insert(key.to_owned(), h.to_string());
 
         self
@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use h2::server::{self, SendResponse};
+use h2::{Reason, RecvStream, SendStream};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+use crate::requests::{MAX_BODY, parse_target};
+use crate::server::State;
+use crate::{
+    Body, ClientError, ErrorStatus, Method, PheasantError, PheasantResult, Protocol, Request,
+    Response, TlsInfo,
+};
+
+// how many streams a client can have open at once on a single connection
+const MAX_STREAMS: u32 = 128;
+
+// headers that are specific to an http/1 connection and are illegal in http/2
+const CONNECTION_HEADERS: [&str; 5] = [
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// checks if the bytes a connection starts with are the http/2 connection preface,
+/// i.e., the client knows beforehand that the server speaks http/2 (h2c)
+///
+/// no http/1 method starts with "PRI "
+pub(crate) fn is_preface(buf: &[u8]) -> bool {
+    buf.starts_with(b"PRI ")
+}
+
+// serves an http/2 connection
+// every stream is dispatched to the services in its own task
+//
+// the keep alive policy applies to the connection as a whole,
+// it is closed once it idles past the timeout without open streams
+// or once it has served the max number of streams
+pub(crate) async fn serve<T>(state: &Arc<State>, io: T, tls: Option<TlsInfo>) -> PheasantResult<()>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut conn = server::Builder::new()
+        .max_concurrent_streams(MAX_STREAMS)
+        .handshake::<_, Bytes>(io)
+        .await
+        .map_err(bad_request)?;
+    let keep_alive = state.keep_alive;
+    let idle = tokio::time::sleep(keep_alive.timeout());
+    tokio::pin!(idle);
+    // the open streams, the connection only idles while there are none
+    let mut streams = JoinSet::new();
+    let mut served = 0;
+    let mut closing = false;
+
+    loop {
+        let accepted = tokio::select! {
+            accepted = conn.accept() => accepted,
+            Some(_) = streams.join_next(), if !streams.is_empty() => {
+                idle.as_mut().reset(Instant::now() + keep_alive.timeout());
+
+                continue;
+            }
+            // tell the client to stop opening streams,
+            // the open ones are still served until they are done
+            _ = state.shutdown.triggered(), if !closing => {
+                conn.graceful_shutdown();
+                closing = true;
+
+                continue;
+            }
+            _ = &mut idle, if !closing && streams.is_empty() => {
+                conn.graceful_shutdown();
+                closing = true;
+
+                continue;
+            }
+        };
+        let (req, respond) = match accepted {
+            Some(Ok(stream)) => stream,
+            Some(Err(e)) => return Err(bad_request(e)),
+            None => break,
+        };
+        served += 1;
+        if served >= keep_alive.max() && !closing {
+            conn.graceful_shutdown();
+            closing = true;
+        }
+
+        let state = state.clone();
+        let tls = tls.clone();
+        streams.spawn(async move {
+            let resp = match request(req).await {
+                Ok(mut req) => {
+                    req.set_tls(tls);
+
+                    state.respond(req).await
+                }
+                Err(err) => {
+                    let status = match err {
+                        PheasantError::ClientError(status) => status,
+                        _ => ClientError::BadRequest,
+                    };
+
+                    state
+                        .failure(ErrorStatus::Client(status), Some(Protocol::HTTP2))
+                        .await
+                }
+            };
+            // fails only if the client reset the stream or the connection is gone
+            _ = send(resp, respond).await;
+        });
+    }
+
+    Ok(())
+}
+
+fn bad_request(_err: h2::Error) -> PheasantError {
+    PheasantError::ClientError(ClientError::BadRequest)
+}
+
+// turns an http/2 stream request into a `Request`
+// the whole body is read before the request is handed to the service,
+// as long as it isn't larger than the max body size
+async fn request(req: http::Request<RecvStream>) -> PheasantResult<Request> {
+    let (parts, mut stream) = req.into_parts();
+
+    let method = Method::try_from(parts.method.as_str())?;
+    let target = parts.uri.path_and_query().map_or("/", |pq| pq.as_str());
+    let resource = parse_target(method, target)?;
+
+    let mut headers = headers_map(&parts.headers)?;
+    // :authority replaces the host header in http/2
+    if let Some(authority) = parts.uri.authority() {
+        headers
+            .entry("Host".into())
+            .or_insert_with(|| authority.to_string());
+    }
+
+    let body = if stream.is_end_stream() {
+        None
+    } else {
+        let mut body = vec![];
+        while let Some(data) = stream.data().await {
+            let data = data.map_err(bad_request)?;
+            if data.len() > MAX_BODY - body.len() {
+                return Err(PheasantError::ClientError(ClientError::ContentTooLarge));
+            }
+            // lets the client send more
+            _ = stream.flow_control().release_capacity(data.len());
+            body.extend_from_slice(&data);
+        }
+
+        Some(String::from_utf8(body)?)
+    };
+    let trailers = match stream.trailers().await.map_err(bad_request)? {
+        Some(ref trailers) => headers_map(trailers)?,
+        None => HashMap::new(),
+    };
+
+    Ok(Request::from_parts(
+        method,
+        Protocol::HTTP2,
+        resource,
+        body,
+        headers,
+        trailers,
+    ))
+}
+
+// http/2 header names are all lowercase,
+// they are converted back to the capitalized form the services look them up with
+fn headers_map(map: &http::HeaderMap) -> PheasantResult<HashMap<String, String>> {
+    let mut headers = HashMap::<String, String>::new();
+
+    for (name, value) in map {
+        let value = value
+            .to_str()
+            .map_err(|_| PheasantError::ClientError(ClientError::BadRequest))?;
+        // cookies can be split into many fields, the rest are joined into a list
+        let separator = if name == http::header::COOKIE {
+            "; "
+        } else {
+            ", "
+        };
+
+        headers
+            .entry(capitalize(name.as_str()))
+            .and_modify(|v| {
+                v.push_str(separator);
+                v.push_str(value);
+            })
+            .or_insert_with(|| value.to_owned());
+    }
+
+    Ok(headers)
+}
+
+// content-type -> Content-Type
+fn capitalize(name: &str) -> String {
+    name.split('-')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// sends the response over its stream
+async fn send(resp: Response, mut respond: SendResponse<Bytes>) -> Result<(), h2::Error> {
+    let head_only = resp.is_head();
+    let (status, headers, body) = resp.into_parts();
+
+    let mut head = http::Response::builder().status(status);
+    for (name, value) in headers {
+        let name = name.to_ascii_lowercase();
+        // the content length is set from the body itself
+        if CONNECTION_HEADERS.contains(&name.as_str()) || name == "content-length" {
+            continue;
+        }
+        head = head.header(name, value);
+    }
+    let len = match body {
+        Some(Body::Bytes(ref body)) => Some(body.len() as u64),
+        Some(Body::Stream(ref stream)) => stream.content_length(),
+        None => None,
+    };
+    if let Some(len) = len {
+        head = head.header(http::header::CONTENT_LENGTH, len);
+    }
+    let head = head
+        .body(())
+        .map_err(|_| h2::Error::from(Reason::INTERNAL_ERROR))?;
+
+    let mut stream = respond.send_response(head, body.is_none() || head_only)?;
+    if head_only {
+        return Ok(());
+    }
+    match body {
+        Some(Body::Bytes(body)) => send_data(&mut stream, body.into(), true).await,
+        Some(Body::Stream(mut body)) => {
+            while let Some(chunk) = body
+                .next_chunk()
+                .await
+                .map_err(|_| h2::Error::from(Reason::INTERNAL_ERROR))?
+            {
+                send_data(&mut stream, chunk.into(), false).await?;
+            }
+
+            stream.send_data(Bytes::new(), true)
+        }
+        None => Ok(()),
+    }
+}
+
+// sends the data as the client's flow control window lets it through
+async fn send_data(
+    stream: &mut SendStream<Bytes>,
+    mut data: Bytes,
+    end: bool,
+) -> Result<(), h2::Error> {
+    // an empty body still has to end the stream
+    if data.is_empty() && end {
+        return stream.send_data(data, true);
+    }
+
+    while !data.is_empty() {
+        stream.reserve_capacity(data.len());
+        let capacity = match std::future::poll_fn(|cx| stream.poll_capacity(cx)).await {
+            Some(capacity) => capacity?,
+            // the stream was reset by the client
+            None => return Err(h2::Error::from(Reason::CANCEL)),
+        };
+        if capacity == 0 {
+            continue;
+        }
+
+        let chunk = data.split_to(capacity.min(data.len()));
+        stream.send_data(chunk, end && data.is_empty())?;
+    }
+
+    Ok(())
+}
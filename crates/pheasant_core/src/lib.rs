@@ -12,32 +12,45 @@ use pheasant_uri::Route;
 // NOTE indefinitely experimental
 // mod monopoly;
 
+pub mod body;
 pub mod cookies;
 pub mod cors;
 pub mod failure;
 pub mod headers;
+mod http2;
+pub mod middleware;
 pub mod mime;
 pub mod requests;
 pub mod response;
+pub mod scope;
 pub mod server;
 pub mod service;
+pub mod sse;
 pub mod status;
 pub mod tls;
+pub mod upgrade;
+pub mod ws;
 
+pub use body::{Body, BodyStream};
 pub use cookies::Cookie;
 pub use cors::Cors;
 pub use failure::Failure;
 pub use headers::{Header, HeaderMap};
+pub use middleware::Middleware;
 pub use mime::Mime;
 pub use requests::Request;
 pub use response::Response;
-pub use server::Server;
+pub use scope::Scope;
+pub use server::{KeepAlive, Load, Server, Shutdown, signals};
 pub use service::Service;
+pub use sse::{Event, Sse};
 pub use status::{
     ClientError, ErrorStatus, Informational, Redirection, ResponseStatus, ServerError, Status,
     Successful,
 };
 pub use tls::*;
+pub use upgrade::{Io, Upgrade};
+pub use ws::{Message, WebSocket, WsReceiver, WsSender, WsUpgrade};
 
 // TODO service macro attr status
 // this lets the user pick their status code of choice for their service's response
@@ -103,10 +116,117 @@ impl From<url::ParseError> for PheasantError {
     }
 }
 
+/// error returned when a service or a failure can't be registered with the server
+///
+/// the registered and new definitions are named after their route and handler function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterError {
+    /// the route pattern of the new service matches the same requests as the one of
+    /// an already registered service, e.g., "GET /users/:id" and "GET /users/{name}"
+    Conflict {
+        method: Method,
+        registered: String,
+        new: String,
+    },
+    /// a route redirects to two services of the same method,
+    /// or it redirects to a service while another one is already routed there
+    Redirect {
+        method: Method,
+        route: String,
+        registered: String,
+        new: String,
+    },
+    /// two failures handle the same status code
+    Failure {
+        code: u16,
+        registered: String,
+        new: String,
+    },
+}
+
+impl RegisterError {
+    pub(crate) fn conflict(registered: &Service, new: &Service) -> Self {
+        Self::Conflict {
+            method: new.method(),
+            registered: describe(registered),
+            new: describe(new),
+        }
+    }
+
+    pub(crate) fn redirect(route: &str, registered: &Service, new: &Service) -> Self {
+        Self::Redirect {
+            method: new.method(),
+            route: route.to_owned(),
+            registered: describe(registered),
+            new: describe(new),
+        }
+    }
+
+    pub(crate) fn failure(registered: &Failure, new: &Failure) -> Self {
+        Self::Failure {
+            code: new.code(),
+            registered: registered.name().to_owned(),
+            new: new.name().to_owned(),
+        }
+    }
+}
+
+// "/users/:id (app::user)"
+fn describe(service: &Service) -> String {
+    format!("{} ({})", service.route(), service.name())
+}
+
+impl fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Conflict {
+                method,
+                registered,
+                new,
+            } => write!(
+                f,
+                "the service {:?} {} conflicts with the already registered {:?} {}",
+                method, new, method, registered
+            ),
+            Self::Redirect {
+                method,
+                route,
+                registered,
+                new,
+            } => write!(
+                f,
+                "the {:?} route {} is claimed by both the already registered service {} and the service {}",
+                method, route, registered, new
+            ),
+            Self::Failure {
+                code,
+                registered,
+                new,
+            } => write!(
+                f,
+                "the failure {} for status {} conflicts with the already registered failure {}",
+                new, code, registered
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RegisterError {}
+
 /// HTTP Method enum
 /// only Get method is somewhat supported at the moment
 #[derive(
-    Debug, Default, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
 )]
 pub enum Method {
     Head,
@@ -224,12 +344,18 @@ impl FromStr for Method {
 
 /// Http protocol version
 ///
-/// currently only http 1.1 is supported
+/// http 1.0 requests are answered in kind: no chunked bodies and no persistent
+/// connections unless asked for
+///
+/// http 2 is spoken over its own binary framing,
+/// either with prior knowledge (h2c) or when negotiated through tls alpn
 #[non_exhaustive]
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Protocol {
+    HTTP1_0,
     #[default]
     HTTP1_1,
+    HTTP2,
 }
 
 impl std::fmt::Display for Protocol {
@@ -238,7 +364,9 @@ impl std::fmt::Display for Protocol {
             f,
             "{}",
             match self {
+                Self::HTTP1_0 => "HTTP/1.0",
                 Self::HTTP1_1 => "HTTP/1.1",
+                Self::HTTP2 => "HTTP/2",
             }
         )
     }
@@ -249,6 +377,7 @@ impl TryFrom<&[u8]> for Protocol {
 
     fn try_from(v: &[u8]) -> Result<Self, Self::Error> {
         match v {
+            b"HTTP/1.0" => Ok(Self::HTTP1_0),
             b"HTTP/1.1" => Ok(Self::HTTP1_1),
             b"HTTP/2" | b"HTTP/3" => Err(Self::Error::ServerError(
                 ServerError::HTTPVersionNotSupported,
@@ -263,6 +392,7 @@ impl TryFrom<&str> for Protocol {
 
     fn try_from(v: &str) -> Result<Self, Self::Error> {
         match v {
+            "HTTP/1.0" => Ok(Self::HTTP1_0),
             "HTTP/1.1" => Ok(Self::HTTP1_1),
             "HTTP/2" | "HTTP/3" => Err(Self::Error::ServerError(
                 ServerError::HTTPVersionNotSupported,
@@ -277,6 +407,7 @@ impl FromStr for Protocol {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
+            "HTTP/1.0" => Ok(Self::HTTP1_0),
             "HTTP/1.1" => Ok(Self::HTTP1_1),
             "HTTP/2" | "HTTP/3" => {
                 Err(Self::Err::ServerError(ServerError::HTTPVersionNotSupported))
@@ -296,21 +427,22 @@ impl ServiceBundle for Service {
     }
 }
 
-impl ServiceBundle for [Service; 2] {
+// bundles of bundles flatten, e.g., `[vec![get, post], vec![put]]`
+impl<B: ServiceBundle, const N: usize> ServiceBundle for [B; N] {
     fn bundle_iter(self) -> std::vec::IntoIter<Service> {
-        Vec::from(self).into_iter()
-    }
-}
-
-impl ServiceBundle for [Service; 3] {
-    fn bundle_iter(self) -> std::vec::IntoIter<Service> {
-        Vec::from(self).into_iter()
+        self.into_iter()
+            .flat_map(B::bundle_iter)
+            .collect::<Vec<_>>()
+            .into_iter()
     }
 }
 
-impl ServiceBundle for Vec<Service> {
+impl<B: ServiceBundle> ServiceBundle for Vec<B> {
     fn bundle_iter(self) -> std::vec::IntoIter<Service> {
         self.into_iter()
+            .flat_map(B::bundle_iter)
+            .collect::<Vec<_>>()
+            .into_iter()
     }
 }
 
@@ -0,0 +1,80 @@
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::{Request, Response};
+
+/// the future type returned by the middleware hooks
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// a layer that wraps the handling of a request
+///
+/// `before` runs ahead of the service, it can inspect or modify the request,
+/// or answer it right away by returning a response, the service is skipped then
+///
+/// `after` runs once the response is ready, it can inspect or modify it
+///
+/// layers are registered on the server, on scopes and on services, see `Server::wrap`,
+/// they run from the outermost to the innermost:
+/// the server layers, then the layers of the outer scopes, of the inner scopes and of the service,
+/// each in the order they were registered in;
+/// their `after` hooks run in the reverse order
+///
+/// when a layer answers the request, only the layers that ran before it get to see the response
+///
+/// ```
+/// # use pheasant_core::middleware::BoxFuture;
+/// # use pheasant_core::{HeaderMap, Middleware, Request, Response};
+/// struct Auth;
+///
+/// impl Middleware for Auth {
+///     fn before<'a>(&'a self, req: &'a mut Request) -> BoxFuture<'a, Option<Response>> {
+///         Box::pin(async move {
+///             (!req.has_header::<String>("Authorization")).then(|| Response::with_status(401))
+///         })
+///     }
+/// }
+/// ```
+pub trait Middleware: Send + Sync + 'static {
+    /// runs before the service, returns a response to answer the request without it
+    fn before<'a>(&'a self, _req: &'a mut Request) -> BoxFuture<'a, Option<Response>> {
+        Box::pin(std::future::ready(None))
+    }
+
+    /// runs after the service, or after the layer that answered the request
+    fn after<'a>(&'a self, _req: &'a Request, _resp: &'a mut Response) -> BoxFuture<'a, ()> {
+        Box::pin(std::future::ready(()))
+    }
+}
+
+// the layers of a server, scope or service, shared with the services they wrap
+pub(crate) type Layers = Vec<Arc<dyn Middleware>>;
+
+// runs the handler through the layers
+pub(crate) async fn layered<F>(
+    layers: &[Arc<dyn Middleware>],
+    req: &mut Request,
+    handler: F,
+) -> Response
+where
+    F: AsyncFnOnce(&mut Request) -> Response,
+{
+    let mut ran = 0;
+    let mut answer = None;
+    for layer in layers {
+        ran += 1;
+        answer = layer.before(req).await;
+        if answer.is_some() {
+            break;
+        }
+    }
+
+    let mut resp = match answer {
+        Some(resp) => resp,
+        None => handler(req).await,
+    };
+    for layer in layers[..ran].iter().rev() {
+        layer.after(req, &mut resp).await;
+    }
+
+    resp
+}
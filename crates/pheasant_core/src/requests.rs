@@ -1,9 +1,14 @@
 use std::collections::HashMap;
-use std::io::{BufRead, BufReader, Read};
-use std::net::TcpStream;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
 
-use super::{ClientError, Header, HeaderMap, Method, PheasantError, PheasantResult, Protocol};
-use pheasant_uri::{Query, Resource, Route};
+use super::{
+    ClientError, Header, HeaderMap, Method, PheasantError, PheasantResult, Protocol, TlsInfo,
+    headers,
+};
+use pheasant_uri::{Params, Query, Resource, Route};
+
+/// the largest request body the server reads, the requests with a larger one get a 413
+pub(crate) const MAX_BODY: usize = 8 * 1024 * 1024;
 
 /// HTTP Request type
 /// used in services to generate service input type; R: From<Request>
@@ -15,6 +20,12 @@ pub struct Request {
     query: Option<Query>,
     body: Option<String>,
     headers: HashMap<String, String>,
+    // trailer fields sent after a chunked body
+    trailers: HashMap<String, String>,
+    // the tls parameters of the connection, None over plaintext
+    tls: Option<TlsInfo>,
+    // the path parameters captured by the route pattern of the matched service
+    params: Params,
 }
 
 impl Request {
@@ -23,20 +34,43 @@ impl Request {
     /// ### Error
     ///
     /// returns a `PheasantError` in case of a bad request
-    pub(crate) fn from_stream(stream: &mut TcpStream) -> PheasantResult<Self> {
+    pub(crate) async fn from_stream<R>(reader: &mut R) -> PheasantResult<Self>
+    where
+        R: AsyncBufRead + Unpin,
+    {
         let mut v = vec![];
-        let mut reader = BufReader::new(stream);
         // if error we return 400 bad request
-        _ = read_req_line(&mut v, &mut reader)?;
+        _ = read_req_line(&mut v, reader).await?;
         let (method, mut resource, proto) = parse_req_line(&mut v.drain(..))?;
         let (route, query) = (resource.take_route(), resource.take_query());
 
-        let headers = read_parse_headers(&mut v, &mut reader)?;
+        let mut headers = read_parse_headers(&mut v, reader).await?;
+        let mut trailers = HashMap::new();
+
+        // http 1.1 made the host header mandatory
+        if proto == Protocol::HTTP1_1 && !headers.has_header::<String>("Host") {
+            return Err(PheasantError::ClientError(ClientError::BadRequest));
+        }
+
+        let body = if let Some(te) = headers.header::<String>("Transfer-Encoding") {
+            // transfer codings don't exist in http 1.0, the body can't be framed
+            if proto == Protocol::HTTP1_0 {
+                return Err(PheasantError::ClientError(ClientError::BadRequest));
+            }
+            // chunked has to be the final transfer coding of a request body,
+            // otherwise the body length can't be determined
+            if !is_chunked(&te) {
+                return Err(PheasantError::ClientError(ClientError::BadRequest));
+            }
+            // the content length, if any, is wrong and must be ignored
+            headers.retain(|name, _| !name.eq_ignore_ascii_case("Content-Length"));
 
-        let len = headers.header::<usize>("Content-Length");
+            trailers = read_chunked_body(&mut v, reader).await?;
+            let b = String::from_utf8(v)?;
 
-        let body = if let Some(len) = len {
-            read_body(&mut v, &mut reader, len)?;
+            Some(b)
+        } else if let Some(len) = headers.header::<usize>("Content-Length") {
+            read_body(&mut v, reader, len).await?;
             let b = String::from_utf8(v)?;
 
             Some(b)
@@ -51,9 +85,39 @@ impl Request {
             query,
             body,
             headers,
+            trailers,
+            tls: None,
+            params: Params::default(),
         })
     }
 
+    // builds a request out of the parts of a request that was not read off an http/1 stream
+    pub(crate) fn from_parts(
+        method: Method,
+        proto: Protocol,
+        mut resource: Resource,
+        body: Option<String>,
+        headers: HashMap<String, String>,
+        trailers: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            method,
+            proto,
+            route: resource.take_route(),
+            query: resource.take_query(),
+            body,
+            headers,
+            trailers,
+            tls: None,
+            params: Params::default(),
+        }
+    }
+
+    /// checks if the request targets the server as a whole, i.e., `OPTIONS *`
+    pub fn targets_server(&self) -> bool {
+        self.route.as_str() == "*"
+    }
+
     /// returns a copy of this request's http Method
     pub fn method(&self) -> Method {
         self.method
@@ -116,6 +180,21 @@ impl Request {
         query.contains_attr(key)
     }
 
+    /// returns the path parameters the route pattern of the service captured,
+    /// e.g., the `id` of "/users/:id"
+    pub fn path_params(&self) -> &Params {
+        &self.params
+    }
+
+    /// returns the value of the path parameter `name` if the route pattern captured it
+    pub fn path_param(&self, name: &str) -> Option<&str> {
+        self.params.get(name)
+    }
+
+    pub(crate) fn set_path_params(&mut self, params: Params) {
+        self.params = params;
+    }
+
     pub fn body(&self) -> Option<&str> {
         self.body.as_ref().map(|s| s.as_str())
     }
@@ -153,12 +232,54 @@ impl Request {
         self.proto
     }
 
+    /// checks if the client wants the connection kept open once this request is answered
+    ///
+    /// http 1.1 connections persist unless the client sends `Connection: close`,
+    /// http 1.0 connections only persist if the client sends `Connection: keep-alive`
+    pub fn keep_alive(&self) -> bool {
+        match self.proto {
+            Protocol::HTTP1_0 => self.connection_has("keep-alive"),
+            _ => !self.connection_has("close"),
+        }
+    }
+
+    // checks if the `Connection` header lists the passed option
+    pub(crate) fn connection_has(&self, option: &str) -> bool {
+        self.header::<String>("Connection").is_some_and(|conn| {
+            conn.split(',')
+                .any(|opt| opt.trim().eq_ignore_ascii_case(option))
+        })
+    }
+
+    /// returns what was negotiated during the tls handshake of the connection,
+    /// `None` if the request came over plaintext
+    pub fn tls(&self) -> Option<&TlsInfo> {
+        self.tls.as_ref()
+    }
+
+    pub(crate) fn set_tls(&mut self, tls: Option<TlsInfo>) {
+        self.tls = tls;
+    }
+
     /// takes this request's headers map and returns them
     ///
     /// once this is used, self.headers becomes an empty `HashMap`
     pub fn headers(&mut self) -> HashMap<String, String> {
         std::mem::take(&mut self.headers)
     }
+
+    /// returns a reference to the trailer field sent after a chunked body if it exists
+    /// Otherwise, returns `None`
+    pub fn trailer(&self, key: &str) -> Option<&str> {
+        headers::find(&self.trailers, key).map(|s| s.as_str())
+    }
+
+    /// takes this request's trailer fields map and returns them
+    ///
+    /// once this is used, self.trailers becomes an empty `HashMap`
+    pub fn trailers(&mut self) -> HashMap<String, String> {
+        std::mem::take(&mut self.trailers)
+    }
 }
 
 impl HeaderMap for Request {
@@ -173,8 +294,32 @@ impl HeaderMap for Request {
     }
 }
 
-fn read_req_line(v: &mut Vec<u8>, s: &mut BufReader<&mut TcpStream>) -> PheasantResult<usize> {
+async fn read_req_line<R>(v: &mut Vec<u8>, s: &mut R) -> PheasantResult<usize>
+where
+    R: AsyncBufRead + Unpin,
+{
     s.read_until(10, v)
+        .await
+        .map_err(|_| PheasantError::ClientError(ClientError::BadRequest))
+}
+
+// parses the request target into the requested resource
+//
+// the asterisk form, `*`, targets the server as a whole rather than one of its resources,
+// it is only valid for OPTIONS requests and is kept as the route "*"
+pub(crate) fn parse_target(method: Method, target: &str) -> PheasantResult<Resource> {
+    if target == "*" {
+        if method != Method::Options {
+            return Err(PheasantError::ClientError(ClientError::BadRequest));
+        }
+        let mut route = Route::default();
+        route.push('*');
+
+        return Ok(Resource::from_parts(route, None));
+    }
+
+    target
+        .parse::<Resource>()
         .map_err(|_| PheasantError::ClientError(ClientError::BadRequest))
 }
 
@@ -195,9 +340,10 @@ fn parse_req_line(
     {
         val.push(b);
     }
-    let Ok(resource) = str::from_utf8(&val).unwrap().parse::<Resource>() else {
+    let Ok(target) = str::from_utf8(&val) else {
         return Err(PheasantError::ClientError(ClientError::BadRequest));
     };
+    let resource = parse_target(method, target)?;
 
     val.clear();
 
@@ -212,13 +358,20 @@ fn parse_req_line(
     Ok((method, resource, proto))
 }
 
-fn read_parse_headers(
+async fn read_parse_headers<R>(
     v: &mut Vec<u8>,
-    s: &mut BufReader<&mut TcpStream>,
-) -> PheasantResult<HashMap<String, String>> {
+    s: &mut R,
+) -> PheasantResult<HashMap<String, String>>
+where
+    R: AsyncBufRead + Unpin,
+{
     let mut map = HashMap::new();
 
-    while let Ok(n) = s.read_until(10, v) {
+    loop {
+        // the connection was closed (or timed out) before the headers ended
+        if s.read_until(10, v).await? == 0 {
+            return Err(PheasantError::ClientError(ClientError::BadRequest));
+        }
         if v.len() <= 2 && Some(&10) == v.last() {
             break;
         }
@@ -249,17 +402,93 @@ fn read_parse_headers(
     Ok(map)
 }
 
-// WARN rn, if no content len header is found, server ignores request body
-// TODO handle body with missing content length
-fn read_body(v: &mut Vec<u8>, s: &mut BufReader<&mut TcpStream>, len: usize) -> PheasantResult<()> {
+async fn read_body<R>(v: &mut Vec<u8>, s: &mut R, len: usize) -> PheasantResult<()>
+where
+    R: AsyncBufRead + Unpin,
+{
+    if len > MAX_BODY {
+        return Err(PheasantError::ClientError(ClientError::ContentTooLarge));
+    }
     v.resize(len, 0);
-    s.read_exact(v)?;
+    s.read_exact(v).await?;
 
     Ok(())
 }
 
+// checks if chunked is the last coding applied to the body
+fn is_chunked(te: &str) -> bool {
+    te.rsplit(',')
+        .next()
+        .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+}
+
+// reads a chunked body into v, the chunks get concatenated in order
+// returns the trailer fields that follow the last chunk
+//
+// chunked-body = *chunk last-chunk trailer-section CRLF
+// chunk = chunk-size [ chunk-ext ] CRLF chunk-data CRLF
+async fn read_chunked_body<R>(v: &mut Vec<u8>, s: &mut R) -> PheasantResult<HashMap<String, String>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    v.clear();
+    let mut line = vec![];
+    loop {
+        line.clear();
+        if s.read_until(10, &mut line).await? == 0 {
+            return Err(PheasantError::ClientError(ClientError::BadRequest));
+        }
+        let size = parse_chunk_size(&line)?;
+        if size == 0 {
+            break;
+        }
+        // the size of the decoded body is capped, not that of a single chunk
+        if size > MAX_BODY - v.len() {
+            return Err(PheasantError::ClientError(ClientError::ContentTooLarge));
+        }
+
+        // the chunk is read as it arrives rather than allocated upfront from the size line
+        let read = (&mut *s).take(size as u64).read_to_end(v).await?;
+        if read != size {
+            return Err(PheasantError::ClientError(ClientError::BadRequest));
+        }
+
+        let mut crlf = [0; 2];
+        s.read_exact(&mut crlf).await?;
+        if crlf != *b"\r\n" {
+            return Err(PheasantError::ClientError(ClientError::BadRequest));
+        }
+    }
+
+    line.clear();
+    read_parse_headers(&mut line, s).await
+}
+
+// parses the hex chunk size off a chunk size line
+// chunk extensions are ignored
+fn parse_chunk_size(line: &[u8]) -> PheasantResult<usize> {
+    let line = str::from_utf8(line)?;
+    let line = line
+        .strip_suffix('\n')
+        .map(|l| l.strip_suffix('\r').unwrap_or(l))
+        .ok_or(PheasantError::ClientError(ClientError::BadRequest))?;
+    let size = line.split(';').next().unwrap_or_default().trim();
+
+    if size.is_empty() || !size.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(PheasantError::ClientError(ClientError::BadRequest));
+    }
+
+    Ok(usize::from_str_radix(size, 16)?)
+}
+
 impl From<&Request> for Request {
     fn from(req: &Request) -> Self {
         req.clone()
     }
 }
+
+impl From<&Request> for Params {
+    fn from(req: &Request) -> Self {
+        req.params.clone()
+    }
+}
@@ -4,9 +4,9 @@ use chrono::{DateTime, offset::Utc};
 use pheasant_uri::{Origin, Resource};
 
 use crate::{
-    ClientError, Cookie, Cors, ErrorStatus, Failure, Header, HeaderMap, Mime, PheasantError,
-    PheasantResult, Protocol, Redirection, Request, ResponseStatus, ServerError, Service, Status,
-    Successful,
+    Body, BodyStream, ClientError, Cookie, Cors, ErrorStatus, Failure, Header, HeaderMap,
+    Informational, Mime, PheasantError, PheasantResult, Protocol, Redirection, Request,
+    ResponseStatus, ServerError, Service, Status, Successful, Upgrade,
 };
 
 const SERVER: &str = "Pheasant (dev/0.1.0)";
@@ -19,6 +19,14 @@ pub enum StatusState {
 }
 
 impl StatusState {
+    // the status that gets sent, a response nobody gave a status to is a 200
+    fn or_ok(&self) -> Status {
+        match self {
+            Self::Status(status) => *status,
+            Self::Pending => Status::Successful(Successful::OK),
+        }
+    }
+
     fn code(&self) -> Option<u16> {
         let Self::Status(s) = self else {
             return None;
@@ -37,13 +45,18 @@ impl StatusState {
 }
 
 /// Http Response type
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+///
+/// neither `Clone` nor `PartialEq`, a streamed body can't be copied or compared
+#[derive(Debug, Default)]
 pub struct Response {
     proto: Protocol,
-    body: Option<Vec<u8>>,
+    body: Option<Body>,
     headers: HashMap<String, String>,
     status: StatusState,
     cookies: HashSet<Cookie>,
+    upgrade: Option<Upgrade>,
+    // answers a HEAD request, the head is sent as is but the body is left out
+    head: bool,
 }
 
 impl Response {
@@ -70,11 +83,12 @@ impl Response {
     // point
     // otherwise, data that is stored in the Response type can be injected into the response
     // bytes inside the Response.respond method
-    pub async fn payload(req: Request, status: Status, service: &Service) -> Self {
-        let mime = mime(&req, service);
+    pub async fn payload(req: &Request, status: Status, service: &Service) -> Self {
+        let mime = mime(req, service);
 
-        let mut resp = (service.service())(&req).await;
-        resp.set_cors(&req, service);
+        let mut resp = (service.service())(req).await;
+        resp.update_proto(req.proto());
+        resp.set_cors(req, service);
         let mime = if resp.has_header::<Mime>("Content-Type") {
             None
         } else {
@@ -83,6 +97,12 @@ impl Response {
 
         let mut resource = req.query().map(|q| q.sequence()).unwrap_or_default();
         resource.insert_str(0, service.route());
+        // a status the service picked itself, e.g., 101 Switching Protocols, wins over the found one
+        // unless the request came through a redirect route, which always redirects
+        let status = match (status, resp.status) {
+            (Status::Redirection(_), _) | (_, StatusState::Pending) => status,
+            (_, StatusState::Status(picked)) => picked,
+        };
         resp.update_status(status, mime, Some(resource));
 
         resp
@@ -134,7 +154,9 @@ impl Response {
     pub async fn not_implemented() -> Self {
         Self {
             status: StatusState::Status(Status::ServerError(ServerError::NotImplemented)),
-            body: Some(b"{ error: 'NotImplemented', code: 501 }".to_vec()),
+            body: Some(Body::Bytes(
+                b"{ error: 'NotImplemented', code: 501 }".to_vec(),
+            )),
             ..Default::default()
         }
     }
@@ -157,13 +179,38 @@ impl Response {
     // }
 
     /// format the response into bytes to be sent to the client
-    pub fn respond(self) -> Vec<u8> {
-        println!("{:?}", self);
+    ///
+    /// a streaming body is not part of the bytes,
+    /// it is returned alongside them to be written after
+    pub fn respond(mut self) -> (Vec<u8>, Option<BodyStream>) {
+        // on a persistent connection, the client relies on the content length
+        // or the chunked encoding to know where this response ends and the next one begins
+        match self.body {
+            Some(Body::Stream(ref stream)) => match stream.content_length() {
+                Some(len) => {
+                    self.set_header("Content-Length", len as usize);
+                }
+                None => {
+                    self.headers.remove("Content-Length");
+                    if self.is_chunked() {
+                        self.set_header::<String>("Transfer-Encoding", "chunked".into());
+                    }
+                }
+            },
+            _ if self.has_body_status() && !self.has_header::<usize>("Content-Length") => {
+                let len = match self.body {
+                    Some(Body::Bytes(ref body)) => body.len(),
+                    _ => 0,
+                };
+                self.set_header("Content-Length", len);
+            }
+            _ => (),
+        }
         let mut payload = format!(
             "{} {} {}\n",
             self.proto,
-            self.status.code().unwrap(),
-            self.status.text().unwrap(),
+            self.status.or_ok().code(),
+            self.status.or_ok().text(),
         );
         let mut iter = self.headers.into_iter();
         while let Some((ref h, ref v)) = iter.next() {
@@ -182,11 +229,33 @@ impl Response {
 
         payload.push('\n');
         let mut payload = payload.into_bytes();
-        if let Some(body) = self.body {
-            payload.extend(body);
+        if self.head {
+            return (payload, None);
         }
+        let stream = match self.body {
+            Some(Body::Bytes(body)) => {
+                payload.extend(body);
+
+                None
+            }
+            Some(Body::Stream(stream)) => Some(stream),
+            None => None,
+        };
 
-        payload
+        (payload, stream)
+    }
+
+    // splits the response into its status code, header fields and body
+    // for protocols that don't send the head as text
+    pub(crate) fn into_parts(self) -> (u16, Vec<(String, String)>, Option<Body>) {
+        let mut headers: Vec<_> = self.headers.into_iter().collect();
+        headers.extend(
+            self.cookies
+                .into_iter()
+                .map(|cookie| ("Set-Cookie".to_owned(), cookie.to_string())),
+        );
+
+        (self.status.or_ok().code(), headers, self.body)
     }
 }
 
@@ -213,8 +282,11 @@ impl Response {
         self
     }
 
-    pub fn update_body(&mut self, data: Vec<u8>) -> &mut Self {
-        self.body = Some(data);
+    /// sets the response body
+    ///
+    /// takes either the body bytes or a `BodyStream`
+    pub fn update_body(&mut self, data: impl Into<Body>) -> &mut Self {
+        self.body = Some(data.into());
 
         self
     }
@@ -234,6 +306,37 @@ impl Response {
         self
     }
 
+    /// sets the handler that takes the connection over once this response is sent
+    ///
+    /// only meaningful with a 101 Switching Protocols status
+    pub fn set_upgrade(&mut self, upgrade: Upgrade) -> &mut Self {
+        self.upgrade = Some(upgrade);
+
+        self
+    }
+
+    // turns the response into the answer to a HEAD request,
+    // the headers, content length included, stay those of the full response
+    pub(crate) fn strip_body(&mut self) -> &mut Self {
+        self.head = true;
+
+        self
+    }
+
+    pub(crate) fn is_head(&self) -> bool {
+        self.head
+    }
+
+    // the upgrade handler, if the response switches protocols
+    pub(crate) fn take_upgrade(&mut self) -> Option<Upgrade> {
+        match self.status {
+            StatusState::Status(Status::Informational(Informational::SwitchingProtocols)) => {
+                self.upgrade.take()
+            }
+            _ => None,
+        }
+    }
+
     pub fn set_cookie(&mut self, cookie: Cookie) -> &mut Self {
         self.cookies.insert(cookie);
 
@@ -252,10 +355,17 @@ impl Response {
     // origin comes from the request headers
     // cors comes from the corresponding service
     pub fn set_cors(&mut self, req: &Request, service: &Service) -> &mut Self {
-        if let Some(cors) = service.cors()
-            && let Some(origin) = req.header::<Origin>("Origin")
-        {
-            let origin = cors.allows_origin(&origin).then(|| &origin);
+        if let Some(cors) = service.cors() {
+            self.apply_cors(cors, req.header::<Origin>("Origin").as_ref());
+        }
+
+        self
+    }
+
+    // sets the cors headers of the policy for a cross origin request
+    pub(crate) fn apply_cors(&mut self, cors: &Cors, origin: Option<&Origin>) -> &mut Self {
+        if let Some(origin) = origin {
+            let origin = cors.allows_origin(origin).then_some(origin);
             self.headers.extend(cors.to_headers(origin));
         }
 
@@ -265,19 +375,53 @@ impl Response {
 
 impl Response {
     fn successful(&mut self, mime: Option<Mime>) {
-        if let Some(ref mut body) = self.body {
-            *body = deflate::deflate_bytes(&body);
-            *body = deflate::deflate_bytes_gzip(&body);
-            let len = body.len();
-
-            self.set_header::<String>("Content-Encoding".into(), "deflate, gzip".into())
-                .set_header("Content-Length".into(), len);
-            if let Some(mime) = mime {
-                self.set_header("Content-Type", mime);
+        match self.body {
+            Some(Body::Bytes(ref mut body)) => {
+                *body = deflate::deflate_bytes(&body);
+                *body = deflate::deflate_bytes_gzip(&body);
+                let len = body.len();
+
+                self.set_header::<String>("Content-Encoding".into(), "deflate, gzip".into())
+                    .set_header("Content-Length".into(), len);
             }
-            self.set_header("Date".into(), Utc::now())
-                .set_header::<String>("Server".into(), SERVER.into());
+            // streams are sent as they come, uncompressed
+            Some(Body::Stream(_)) => (),
+            None => return,
+        }
+
+        if let Some(mime) = mime {
+            self.set_header("Content-Type", mime);
         }
+        self.set_header("Date".into(), Utc::now())
+            .set_header::<String>("Server".into(), SERVER.into());
+    }
+
+    // 1xx, 204 and 304 responses never carry a body nor a content length
+    fn has_body_status(&self) -> bool {
+        !matches!(
+            self.status,
+            StatusState::Status(
+                Status::Informational(_)
+                    | Status::Successful(Successful::NoContent)
+                    | Status::Redirection(Redirection::NotModified)
+            )
+        )
+    }
+
+    // checks if the body is sent with the chunked coding
+    // http 1.0 clients don't know it, unsized bodies are sent as they are
+    pub(crate) fn is_chunked(&self) -> bool {
+        self.proto != Protocol::HTTP1_0 && self.is_unsized()
+    }
+
+    // checks if the client can tell where the response ends
+    // without the connection being closed
+    pub(crate) fn is_delimited(&self) -> bool {
+        !self.is_unsized() || self.is_chunked()
+    }
+
+    fn is_unsized(&self) -> bool {
+        matches!(self.body, Some(Body::Stream(ref stream)) if stream.content_length().is_none())
     }
 
     fn redirection(&mut self, resource: String) {
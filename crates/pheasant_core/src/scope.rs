@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use crate::middleware::Layers;
+use crate::{Cors, Middleware, Mime, Service, ServiceBundle};
+
+/// a group of services that share a route prefix
+///
+/// scopes nest, the prefixes of the outer scopes are prepended to the inner ones,
+/// e.g., the "/users/:id" service of the "/v1" scope mounted in the "/api" scope
+/// serves "/api/v1/users/:id"
+///
+/// the cors policy and mime type of a scope apply to its services that don't have their own,
+/// an inner scope's settings take precedence over the outer one's
+///
+/// the middleware of a scope wraps all of its services, inside the layers of the outer scopes
+///
+/// ```ignore
+/// let mut v1 = Scope::new("/v1");
+/// v1.service(user).service(users);
+///
+/// let mut api = Scope::new("/api");
+/// api.mime("application/json".parse().unwrap()).scope(v1);
+///
+/// server.mount(api);
+/// ```
+#[derive(Default)]
+pub struct Scope {
+    // the prefix without a trailing slash, "" for the root scope
+    prefix: String,
+    services: Vec<Service>,
+    cors: Option<Cors>,
+    mime: Option<Mime>,
+    middleware: Layers,
+}
+
+impl Scope {
+    /// creates a new scope for the route prefix, e.g., "/api/v1"
+    ///
+    /// the prefix has the form of a route, it can also have path parameters
+    pub fn new(prefix: &str) -> Self {
+        let prefix = prefix.trim_end_matches('/');
+        let prefix = match prefix.starts_with('/') || prefix.is_empty() {
+            true => prefix.to_owned(),
+            false => format!("/{}", prefix),
+        };
+
+        Self {
+            prefix,
+            ..Default::default()
+        }
+    }
+
+    /// returns the route prefix of the scope
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// adds a service to the scope, its route and redirects are relative to the scope prefix
+    pub fn service<S, B>(&mut self, s: S) -> &mut Self
+    where
+        S: Fn() -> B,
+        B: ServiceBundle,
+    {
+        self.services.extend(s().bundle_iter());
+
+        self
+    }
+
+    /// nests a scope inside this one
+    pub fn scope(&mut self, scope: Scope) -> &mut Self {
+        self.services.extend(scope.bundle_iter());
+
+        self
+    }
+
+    /// sets the cors policy of the scope services that don't have one
+    pub fn cors(&mut self, cors: Cors) -> &mut Self {
+        self.cors = Some(cors);
+
+        self
+    }
+
+    /// sets the mime type of the scope services that don't have one
+    pub fn mime(&mut self, mime: Mime) -> &mut Self {
+        self.mime = Some(mime);
+
+        self
+    }
+
+    /// wraps the scope services in a middleware layer, see `Middleware`
+    pub fn wrap(&mut self, middleware: impl Middleware) -> &mut Self {
+        self.middleware.push(Arc::new(middleware));
+
+        self
+    }
+
+    /// the number of services in the scope, nested scopes included
+    pub fn len(&self) -> usize {
+        self.services.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.services.is_empty()
+    }
+}
+
+impl ServiceBundle for Scope {
+    fn bundle_iter(self) -> std::vec::IntoIter<Service> {
+        let Self {
+            prefix,
+            mut services,
+            cors,
+            mime,
+            middleware,
+        } = self;
+        for service in &mut services {
+            service.nest(&prefix);
+            service.wrap_outer(&middleware);
+            if let Some(cors) = &cors {
+                service.default_cors(cors);
+            }
+            if let Some(mime) = &mime {
+                service.default_mime(mime);
+            }
+        }
+
+        services.into_iter()
+    }
+}
@@ -1,22 +1,341 @@
-use std::io::{BufRead, BufReader, BufWriter, Read, Write};
-use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::collections::HashMap;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
 
-use super::{
-    ClientError, Failure, Method, PheasantError, PheasantResult, Protocol, Redirection, Request,
-    Response, ResponseStatus, Route, ServerError, Service, ServiceBundle, Status, Successful,
+use pheasant_uri::{Origin, Params, Router};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+    ReadBuf,
 };
+use tokio::net::TcpStream;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, watch};
+use tokio::task::JoinSet;
+use tokio::time::Sleep;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::server::TlsStream;
 
-// TODO dont allow the registration of 2 Services that point to the same Route
-// TODO dont allow the registration of 2 Failures that handle the same Status
+use super::http2;
+use super::middleware::{Layers, layered};
+use super::{
+    ClientError, Cors, ErrorStatus, Failure, HeaderMap, Method, Middleware, PheasantError,
+    PheasantResult, Protocol, Redirection, RegisterError, ReloadableTls, Request, Response,
+    ResponseStatus, Route, Scope, ServerError, Service, ServiceBundle, Status, Successful, TlsInfo,
+    upgrade::Io,
+};
 
 /// the http server type
 pub struct Server {
-    /// the server tcp listener socket
-    socket: TcpListener,
+    /// the server tcp listener socket,
+    /// handed over to the tokio runtime while serving and closed once the server stops
+    socket: Option<TcpListener>,
+    /// the address the socket is bound to, a new serve binds it again
+    addr: SocketAddr,
+    /// the server services, failures and settings
+    /// every connection task holds a handle to these
+    state: Arc<State>,
+    /// the connections limit and the live count of handled connections
+    load: Load,
+    /// how many connections can wait for a free slot once the limit is reached
+    backlog: usize,
+    /// how long a shutdown waits for in-flight connections before giving up on them
+    grace: Duration,
+    /// wraps the accepted connections in tls, they stay plaintext if unset
+    tls: Option<ReloadableTls>,
+}
+
+// the server state shared between all the connection tasks
+pub(crate) struct State {
     /// container for the server services
     services: Vec<Service>,
+    // the services lookup tables
+    routes: Routes,
     // container for the server error responses (client/server errors)
     errors: Vec<Failure>,
+    /// persistent connections policy
+    pub(crate) keep_alive: KeepAlive,
+    // the cors policy of the routes whose services don't have one of their own
+    cors: Option<Cors>,
+    // the layers every request goes through, the matched service's own come after them
+    middleware: Layers,
+    /// tells the connection tasks that the server is shutting down
+    pub(crate) shutdown: Shutdown,
+    // reports the errors that have no client to be answered to
+    on_error: Option<Box<OnError>>,
+}
+
+// the hook the server errors are reported to
+type OnError = dyn Fn(&PheasantError) + Send + Sync;
+
+// the lookup tables of the registered services, they hold indexes into `State::services`
+// kept up to date as services get registered, so a lookup never scans the services
+#[derive(Default)]
+struct Routes {
+    // the route patterns of every method
+    patterns: HashMap<Method, Router<usize>>,
+    // the services every redirected route leads to
+    redirects: HashMap<String, Vec<(Method, usize)>>,
+}
+
+impl Routes {
+    fn insert(&mut self, service: &Service, idx: usize) {
+        _ = self
+            .patterns
+            .entry(service.method())
+            .or_default()
+            .insert(service.pattern().clone(), idx);
+        for route in service.re().into_iter().flatten() {
+            self.redirects
+                .entry(route.as_str().to_owned())
+                .or_default()
+                .push((service.method(), idx));
+        }
+    }
+
+    // the registered service whose pattern conflicts with the one of `service`
+    fn conflict(&self, service: &Service) -> Option<usize> {
+        self.patterns
+            .get(&service.method())?
+            .conflict(service.pattern())
+            .copied()
+    }
+
+    fn find(&self, method: Method, route: &str) -> Option<(usize, Params)> {
+        let (idx, params) = self.patterns.get(&method)?.find(route)?;
+
+        Some((*idx, params))
+    }
+
+    fn redirects(&self, route: &str) -> impl Iterator<Item = (Method, usize)> + Clone {
+        self.redirects.get(route).into_iter().flatten().copied()
+    }
+
+    // the methods that have a service for the route, in declaration order
+    fn allowed(&self, route: &str) -> Vec<Method> {
+        let methods = self
+            .patterns
+            .iter()
+            .filter(|(_, router)| router.find(route).is_some())
+            .map(|(method, _)| *method)
+            .chain(self.redirects(route).map(|(method, _)| method));
+
+        with_head(methods.collect())
+    }
+
+    // the methods that have a service for any route, in declaration order
+    fn methods(&self) -> Vec<Method> {
+        let methods = self
+            .patterns
+            .keys()
+            .copied()
+            .chain(self.redirects.values().flatten().map(|(method, _)| *method));
+
+        with_head(methods.collect())
+    }
+
+    // the service of the method that the route redirects to
+    fn redirect(&self, method: Method, route: &str) -> Option<usize> {
+        self.redirects(route)
+            .find_map(|(m, idx)| (m == method).then_some(idx))
+    }
+
+    // the redirected routes of the method along with the services they lead to
+    fn redirected(&self, method: Method) -> impl Iterator<Item = (&str, usize)> {
+        self.redirects.iter().flat_map(move |(route, services)| {
+            services
+                .iter()
+                .filter(move |(m, _)| *m == method)
+                .map(move |(_, idx)| (route.as_str(), *idx))
+        })
+    }
+}
+
+/// persistent (keep-alive) http connections policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepAlive {
+    /// how long an open connection can idle waiting for the next request,
+    /// and how long a started request can go without sending more of itself
+    timeout: Duration,
+    /// how many requests a single connection can serve before it gets closed
+    max: usize,
+}
+
+impl Default for KeepAlive {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            max: 100,
+        }
+    }
+}
+
+impl KeepAlive {
+    pub fn new(timeout: Duration, max: usize) -> Self {
+        Self { timeout, max }
+    }
+
+    /// returns the connection idle timeout
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// returns the max number of requests served per connection
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    // the `Keep-Alive` response header value
+    fn header(&self) -> String {
+        format!("timeout={}, max={}", self.timeout.as_secs(), self.max)
+    }
+}
+
+/// a handle to the server's connection counts
+///
+/// cheap to clone, can be queried from any task while the server is serving
+#[derive(Debug, Clone)]
+pub struct Load {
+    /// one permit per connection the server can handle at once
+    permits: Arc<Semaphore>,
+    /// the max number of connections handled at once
+    max: usize,
+    /// connections waiting in the backlog for a permit
+    queued: Arc<AtomicUsize>,
+    /// one permit per refused connection being answered with a 503,
+    /// there can be as many of them as handled connections
+    refusals: Arc<Semaphore>,
+}
+
+impl Load {
+    fn new(max: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max)),
+            max,
+            queued: Arc::new(AtomicUsize::new(0)),
+            refusals: Arc::new(Semaphore::new(max)),
+        }
+    }
+
+    /// returns the max number of connections the server handles at once
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// returns the number of connections currently being handled
+    pub fn in_flight(&self) -> usize {
+        self.max - self.permits.available_permits()
+    }
+
+    /// returns the number of connections waiting in the backlog
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    /// returns the number of refused connections being answered with a 503
+    pub fn refusing(&self) -> usize {
+        self.max - self.refusals.available_permits()
+    }
+
+    // takes a free connection slot
+    // if there are none, waits for one in the backlog as long as it isn't full
+    //
+    // returns None when the connection should be refused
+    async fn admit(&self, backlog: usize) -> Option<OwnedSemaphorePermit> {
+        if let Ok(permit) = self.permits.clone().try_acquire_owned() {
+            return Some(permit);
+        }
+
+        if self.queued.fetch_add(1, Ordering::SeqCst) >= backlog {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+
+            return None;
+        }
+        let permit = self.permits.clone().acquire_owned().await.ok();
+        self.queued.fetch_sub(1, Ordering::SeqCst);
+
+        permit
+    }
+
+    // takes a slot to answer a refused connection with
+    //
+    // returns None when too many are being answered already,
+    // the connection should then be closed without a response
+    fn refusal(&self) -> Option<OwnedSemaphorePermit> {
+        self.refusals.clone().try_acquire_owned().ok()
+    }
+}
+
+/// a handle that can stop a serving server
+///
+/// once triggered, the server stops accepting connections,
+/// finishes the requests in flight and returns from `serve`
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    signal: Arc<watch::Sender<bool>>,
+}
+
+impl Shutdown {
+    fn new() -> Self {
+        Self {
+            signal: Arc::new(watch::Sender::new(false)),
+        }
+    }
+
+    /// triggers the server shutdown
+    pub fn shutdown(&self) {
+        self.signal.send_replace(true);
+    }
+
+    /// checks if the shutdown was triggered
+    pub fn is_shutting_down(&self) -> bool {
+        *self.signal.borrow()
+    }
+
+    // rearms the handle once the server is done shutting down
+    fn reset(&self) {
+        self.signal.send_replace(false);
+    }
+
+    // resolves once the shutdown is triggered
+    pub(crate) async fn triggered(&self) {
+        let mut rx = self.signal.subscribe();
+        _ = rx.wait_for(|down| *down).await;
+    }
+}
+
+/// resolves once the process receives a SIGINT (ctrl-c) or a SIGTERM
+///
+/// ```no_run
+/// # use pheasant_core::{Server, signals};
+/// # async fn serve(mut server: Server) {
+/// server.serve_with_shutdown(signals()).await;
+/// # }
+/// ```
+pub async fn signals() {
+    let interrupt = async {
+        _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => _ = term.recv().await,
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => (),
+        _ = terminate => (),
+    }
 }
 
 // WARN when responding to a credentialed request, the CORS glob/* header value is not allowed for the following headers
@@ -27,54 +346,319 @@ impl Server {
     /// creates a new server
     ///
     /// ```
-    /// let (addr, port) = ([127.0.0.1], 8883);
-    /// let workers = 90000;
-    /// let server = Server::new(workers, addr, port)
+    /// # use pheasant_core::Server;
+    /// let (addr, port) = ([127, 0, 0, 1], 8883);
+    /// let max = 64;
+    /// let server = Server::new(addr, port, max).unwrap();
     /// ```
     ///
+    /// `max` is the number of connections the server handles at once,
+    /// connections past it wait in the backlog (see `Server::backlog`)
+    /// or get refused with a 503 Service Unavailable
+    ///
     /// ### Error
     ///
     pub fn new(addr: impl Into<Ipv4Addr>, mut port: u16, max: usize) -> PheasantResult<Self> {
-        Ok(Self {
-            socket: {
-                let addr = addr.into();
-                let mut socket = TcpListener::bind((addr, port));
-                while socket.is_err() {
-                    port += 1;
-                    socket = TcpListener::bind((addr, port));
-                }
+        let socket = {
+            let addr = addr.into();
+            let mut socket = TcpListener::bind((addr, port));
+            while socket.is_err() {
+                port += 1;
+                socket = TcpListener::bind((addr, port));
+            }
+
+            println!(
+                "\x1b[1;38;2;237;203;244mServer bound at http://{}:{}\x1b[0m",
+                addr, port
+            );
 
-                println!(
-                    "\x1b[1;38;2;237;203;244mServer bound at http://{}:{}\x1b[0m",
-                    addr, port
-                );
+            socket?
+        };
 
-                socket?
-            },
-            services: vec![],
-            errors: vec![],
+        Ok(Self {
+            addr: socket.local_addr()?,
+            socket: Some(socket),
+            state: Arc::new(State {
+                services: vec![],
+                routes: Routes::default(),
+                errors: vec![],
+                keep_alive: KeepAlive::default(),
+                cors: None,
+                middleware: vec![],
+                shutdown: Shutdown::new(),
+                on_error: None,
+            }),
+            load: Load::new(max.max(1)),
+            backlog: 0,
+            grace: Duration::from_secs(30),
+            tls: None,
         })
     }
 
+    /// the address the server is bound to
+    ///
+    /// the port can be past the one passed to `Server::new` if that one was taken
+    pub fn local_addr(&self) -> PheasantResult<SocketAddr> {
+        Ok(self.addr)
+    }
+
+    /// serves https, every accepted connection goes through a tls handshake
+    /// before its requests are read
+    ///
+    /// the negotiated parameters are available to services through `TlsInfo`,
+    /// connections that negotiate h2 through alpn are served over http/2
+    ///
+    /// takes either a rustls `ServerConfig` or a `ReloadableTls`
+    /// whose reloads apply to the following handshakes
+    ///
+    /// ```no_run
+    /// # use pheasant_core::{Server, tls};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut server = Server::new([127, 0, 0, 1], 8443, 64)?;
+    /// server.tls(tls::self_signed_config(&["localhost".into()])?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tls(&mut self, tls: impl Into<ReloadableTls>) -> &mut Self {
+        self.tls = Some(tls.into());
+
+        self
+    }
+
+    /// sets how long a shutdown waits for the connections in flight to finish
+    ///
+    /// connections still open after that are aborted, mid request if need be
+    pub fn grace_period(&mut self, grace: Duration) -> &mut Self {
+        self.grace = grace;
+
+        self
+    }
+
+    /// returns a handle that can shut the server down from another task
+    pub fn shutdown_handle(&self) -> Shutdown {
+        self.state.shutdown.clone()
+    }
+
+    /// sets how many connections can wait for a free slot
+    /// once the server is handling `max` connections
+    ///
+    /// defaults to 0, i.e., connections over the limit are refused right away
+    pub fn backlog(&mut self, backlog: usize) -> &mut Self {
+        self.backlog = backlog;
+
+        self
+    }
+
+    /// returns a handle to the server's live connection counts
+    pub fn load(&self) -> Load {
+        self.load.clone()
+    }
+
+    /// returns the number of connections currently being handled
+    pub fn in_flight(&self) -> usize {
+        self.load.in_flight()
+    }
+
+    // the server can only be configured while none of its connection tasks are alive
+    fn state_mut(&mut self) -> &mut State {
+        Arc::get_mut(&mut self.state)
+            .expect("the server can't be configured while it still has open connections")
+    }
+
+    /// sets the persistent connections policy of the server
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use pheasant_core::Server;
+    /// # let mut server = Server::new([127, 0, 0, 1], 8080, 64).unwrap();
+    /// // idle connections are dropped after 10 seconds,
+    /// // busy ones after they have served 50 requests
+    /// server.keep_alive(Duration::from_secs(10), 50);
+    /// ```
+    ///
+    /// a `max` of 1 turns persistent connections off
+    pub fn keep_alive(&mut self, timeout: Duration, max: usize) -> &mut Self {
+        self.state_mut().keep_alive = KeepAlive::new(timeout, max.max(1));
+
+        self
+    }
+
+    /// sets the default cors policy of the server
+    ///
+    /// it applies to the routes whose services don't have a cors policy of their own,
+    /// their preflight requests get answered by the server
+    /// unless an OPTIONS service is registered for the route
+    ///
+    /// ```no_run
+    /// # use pheasant_core::{Cors, Method, Server};
+    /// # let mut server = Server::new([127, 0, 0, 1], 8080, 64).unwrap();
+    /// let mut cors = Cors::new();
+    /// cors.methods().extend([Method::Get, Method::Post]);
+    /// server.cors(cors);
+    /// ```
+    pub fn cors(&mut self, cors: Cors) -> &mut Self {
+        self.state_mut().cors = Some(cors);
+
+        self
+    }
+
+    /// wraps every request in a middleware layer, see `Middleware`
+    ///
+    /// server layers run before those of the scopes and services,
+    /// they also see the requests no service handles, e.g., 404s
+    ///
+    /// ```no_run
+    /// # use pheasant_core::{Middleware, Server};
+    /// # struct Logger;
+    /// # impl Middleware for Logger {}
+    /// # struct Auth;
+    /// # impl Middleware for Auth {}
+    /// # let mut server = Server::new([127, 0, 0, 1], 8080, 64).unwrap();
+    /// server.wrap(Logger).wrap(Auth);
+    /// ```
+    pub fn wrap(&mut self, middleware: impl Middleware) -> &mut Self {
+        self.state_mut().middleware.push(Arc::new(middleware));
+
+        self
+    }
+
+    /// sets the hook the errors the server can't answer a client with are reported to,
+    /// e.g., a failed accept or a connection that broke mid response
+    ///
+    /// they are dropped if no hook is set
+    ///
+    /// ```no_run
+    /// # use pheasant_core::Server;
+    /// # let mut server = Server::new([127, 0, 0, 1], 8080, 64).unwrap();
+    /// server.on_error(|e| eprintln!("{}", e));
+    /// ```
+    pub fn on_error<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&PheasantError) + Send + Sync + 'static,
+    {
+        self.state_mut().on_error = Some(Box::new(hook));
+
+        self
+    }
+
     /// pushes a new service to the server
+    ///
+    /// ### Panic
+    ///
+    /// panics if one of the services conflicts with an already registered one,
+    /// see `Server::try_service`
     pub fn service<S, B>(&mut self, s: S) -> &mut Self
     where
         S: Fn() -> B,
         B: ServiceBundle,
     {
-        self.services.extend(s().bundle_iter());
+        if let Err(e) = self.try_service(s) {
+            panic!("{}", e);
+        }
+
+        self
+    }
+
+    /// pushes a new service to the server
+    ///
+    /// ### Error
+    ///
+    /// returns a `RegisterError` if a service collides with an already registered one
+    /// of the same method, none of the bundle services are registered then
+    /// - `Conflict`: the route patterns match the same requests,
+    ///   e.g., "/users/:id" and "/users/{name}"
+    /// - `Redirect`: a redirect route of one service is also a redirect route of the other,
+    ///   or is matched by the other's route pattern
+    pub fn try_service<S, B>(&mut self, s: S) -> Result<&mut Self, RegisterError>
+    where
+        S: Fn() -> B,
+        B: ServiceBundle,
+    {
+        self.register(s())
+    }
+
+    /// mounts the services of a scope on the server
+    ///
+    /// ### Panic
+    ///
+    /// panics if a scope service collides with an already registered one,
+    /// see `Server::try_mount`
+    pub fn mount(&mut self, scope: Scope) -> &mut Self {
+        if let Err(e) = self.register(scope) {
+            panic!("{}", e);
+        }
 
         self
     }
 
+    /// mounts the services of a scope on the server
+    ///
+    /// ### Error
+    ///
+    /// returns a `RegisterError` if a scope service collides with an already registered one,
+    /// none of the scope services are registered then, see `Server::try_service`
+    pub fn try_mount(&mut self, scope: Scope) -> Result<&mut Self, RegisterError> {
+        self.register(scope)
+    }
+
+    // registers all the bundle services, or none if any of them collides
+    fn register(&mut self, bundle: impl ServiceBundle) -> Result<&mut Self, RegisterError> {
+        let bundle = bundle.bundle_iter().collect::<Vec<_>>();
+        let state = self.state_mut();
+        for (idx, new) in bundle.iter().enumerate() {
+            let collision = state.collision(new).or_else(|| {
+                bundle[..idx]
+                    .iter()
+                    .find_map(|registered| collision(registered, new))
+            });
+            if let Some(e) = collision {
+                return Err(e);
+            }
+        }
+        for service in bundle {
+            state.routes.insert(&service, state.services.len());
+            state.services.push(service);
+        }
+
+        Ok(self)
+    }
+
+    /// pushes a new failure to the server
+    ///
+    /// ### Panic
+    ///
+    /// panics if a failure is already registered for the same status code,
+    /// see `Server::try_error`
     pub fn error<E>(&mut self, e: E) -> &mut Self
     where
         E: Fn() -> Failure,
     {
-        self.errors.push(e());
+        if let Err(e) = self.try_error(e) {
+            panic!("{}", e);
+        }
 
         self
     }
+
+    /// pushes a new failure to the server
+    ///
+    /// ### Error
+    ///
+    /// returns a `RegisterError::Failure` if a failure is already registered
+    /// for the same status code
+    pub fn try_error<E>(&mut self, e: E) -> Result<&mut Self, RegisterError>
+    where
+        E: Fn() -> Failure,
+    {
+        let new = e();
+        let state = self.state_mut();
+        if let Some(registered) = state.errors.iter().find(|f| f.code() == new.code()) {
+            return Err(RegisterError::failure(registered, &new));
+        }
+        state.errors.push(new);
+
+        Ok(self)
+    }
 }
 
 impl Server {
@@ -82,98 +666,699 @@ impl Server {
     /// returns `(Status, &Service)`
     ///
     /// ### Error
-    /// returns an Err, a client error (404 not found) if the service is not found
+    /// returns an Err, a client error, if the service is not found:
+    /// 405 method not allowed if the route has services for other methods, 404 not found otherwise
     pub fn service_status(
         &self,
         method: Method,
         route: &str,
     ) -> PheasantResult<(Status, &Service)> {
-        match self
-            .services
-            .iter()
-            // .inspect(|s| println!("{}:{:?}", s.route(), s.re()))
-            .find(move |s| {
-                if method == Method::Options {
-                    s.method() == Method::Options
-                        && (s.route() == route
-                            || self.services.iter().any(|s| s.redirects_to(route)))
-                } else {
-                    s.method() == method && (s.route() == route || s.redirects_to(&route))
-                }
-            }) {
-            Some(s) if s.route() == route => Ok((Status::Successful(Successful::OK), s)),
-            Some(s) if s.redirects_to(&route) => {
-                Ok((Status::Redirection(Redirection::SeeOther), s))
-            }
-            Some(s) if s.method() == Method::Options => {
-                Ok((Status::Successful(Successful::NoContent), s))
-            }
-            None => Err(PheasantError::ClientError(ClientError::NotFound)),
-            Some(_) => unreachable!(
-                "logic break: the Service that matches the conditions didnt match the condititons"
-            ),
-        }
+        self.state
+            .service_status(method, route)
+            .map(|(status, service, _)| (status, service))
+    }
+
+    /// returns the methods that have a service for the route,
+    /// i.e., the value of the `Allow` header of a 405 response
+    pub fn allowed_methods(&self, route: &str) -> Vec<Method> {
+        self.state.allowed(route)
     }
 
     /// searches for the speficied `Fail` (error status fallback service)
     /// returns `Some(&Fail)` if found
     /// else returns `None`
     pub fn fail_status(&self, status_code: u16) -> Option<&Failure> {
-        self.errors.iter().find(move |e| e.code() == status_code)
+        self.state.fail_status(status_code)
     }
 
     /// launch the service
     /// listening for incoming tcp streams
-    /// and handling them
+    /// and handling each of them in its own task
+    ///
+    /// runs until the shutdown is triggered from a `Shutdown` handle
     pub async fn serve(&mut self) {
-        for stream in self.socket.incoming().flatten() {
-            if let Err(e) = self.handle_stream(stream).await {
-                // TODO log the error or something
-                println!("{:?}", e);
-            }
-        }
+        self.serve_with_shutdown(std::future::pending()).await
     }
 
-    // handles a tcp stream connection
-    async fn handle_stream(&self, mut stream: TcpStream) -> PheasantResult<TcpStream> {
-        let req = Request::from_stream(&mut stream);
-        println!("{:#?}\n", req); // if req is err we return a status error response
-        let Ok(req) = req else {
-            let resp = self.error_template(400, None).await;
+    /// launch the service until the process receives a SIGINT or a SIGTERM
+    pub async fn serve_with_signals(&mut self) {
+        self.serve_with_shutdown(signals()).await
+    }
 
-            return send_response(stream, resp);
+    /// launch the service until `signal` resolves
+    /// or the shutdown is triggered from a `Shutdown` handle
+    ///
+    /// the server then closes its socket
+    /// and waits for the connections in flight to finish, for up to the grace period
+    ///
+    /// once it returns, the `Shutdown` handles are rearmed and the server can be served again,
+    /// on the same address
+    ///
+    /// returns right away if the address can't be bound again,
+    /// the error is reported to the `Server::on_error` hook
+    pub async fn serve_with_shutdown<F>(&mut self, signal: F)
+    where
+        F: Future<Output = ()>,
+    {
+        let listener = match self.listener() {
+            Ok(listener) => listener,
+            Err(e) => return self.state.report(e.into()),
         };
+        let shutdown = self.state.shutdown.clone();
+        // the connection tasks, kept around so the ones past the grace period can be aborted
+        let mut connections = JoinSet::new();
+        tokio::pin!(signal);
 
-        let resp = match self.service_status(req.method(), req.route()) {
-            Ok((status, service)) => Response::payload(req, status, service).await,
-            Err(PheasantError::ClientError(ClientError::NotFound)) => {
-                self.error_template(404, Some(req.proto())).await
-            }
-            _ => unimplemented!("not implemented yet"),
+        loop {
+            let accepted = tokio::select! {
+                _ = &mut signal => break,
+                _ = shutdown.triggered() => break,
+                accepted = listener.accept() => accepted,
+                // reaps the finished connection tasks
+                Some(_) = connections.join_next(), if !connections.is_empty() => continue,
+            };
+            let stream = match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    self.state.report(e.into());
+                    continue;
+                }
+            };
+
+            let state = self.state.clone();
+            let (load, backlog) = (self.load.clone(), self.backlog);
+            // the config is picked at accept time, a reload doesn't touch open connections
+            let tls = self.tls.as_ref().map(|tls| TlsAcceptor::from(tls.config()));
+            connections.spawn(async move {
+                let permit = load.admit(backlog).await;
+                // a flood of refused connections can't pile up tasks,
+                // those past the cap are closed right away
+                let _refusal = match permit {
+                    Some(_) => None,
+                    None => match load.refusal() {
+                        Some(refusal) => Some(refusal),
+                        None => return,
+                    },
+                };
+                let res = match tls {
+                    Some(acceptor) => match state.handshake(&acceptor, stream).await {
+                        Some((stream, info)) => state.connection(stream, Some(info), permit).await,
+                        None => Ok(()),
+                    },
+                    None => state.connection(stream, None, permit).await,
+                };
+                if let Err(e) = res {
+                    state.report(e);
+                }
+            });
+        }
+
+        // stops the kernel from queueing new connections that would never get accepted
+        drop(listener);
+        shutdown.shutdown();
+        self.drain(connections).await;
+        // the server can be served again
+        shutdown.reset();
+    }
+
+    // waits for the connections to finish for up to the grace period,
+    // the ones still open after it are aborted
+    async fn drain(&self, mut connections: JoinSet<()>) {
+        let drained = async { while connections.join_next().await.is_some() {} };
+
+        if tokio::time::timeout(self.grace, drained).await.is_err() {
+            connections.shutdown().await;
+        }
+    }
+
+    // hands the bound socket over to the tokio runtime,
+    // the socket was closed by the previous serve if there was one, so it's bound again
+    fn listener(&mut self) -> std::io::Result<tokio::net::TcpListener> {
+        let socket = match self.socket.take() {
+            Some(socket) => socket,
+            None => TcpListener::bind(self.addr)?,
         };
+        socket.set_nonblocking(true)?;
 
-        send_response(stream, resp)
+        tokio::net::TcpListener::from_std(socket)
     }
 
     // TODO this and Response::from_err have become redundant since (Failure.callback)() now returns
     // a Response
     // TODO fix Response mess
     pub async fn error_template(&self, code: u16, proto: Option<Protocol>) -> Response {
+        self.state.error_template(code, proto).await
+    }
+}
+
+impl State {
+    // finds the service that handles the request along with the path parameters it captured
+    // a route matching the patterns of many services goes to the most specific one
+    fn service_status(
+        &self,
+        method: Method,
+        route: &str,
+    ) -> PheasantResult<(Status, &Service, Params)> {
+        // a HEAD request without its own service runs the GET one,
+        // the body is stripped from the response
+        let head = |method| match method {
+            Method::Head => Some(Method::Get),
+            _ => None,
+        };
+
+        let found = self
+            .routes
+            .find(method, route)
+            .or_else(|| self.routes.find(head(method)?, route));
+        if let Some((idx, params)) = found {
+            return Ok((
+                Status::Successful(Successful::OK),
+                &self.services[idx],
+                params,
+            ));
+        }
+
+        let redirect = self
+            .routes
+            .redirect(method, route)
+            .or_else(|| self.routes.redirect(head(method)?, route));
+        if let Some(idx) = redirect {
+            return Ok((
+                Status::Redirection(Redirection::SeeOther),
+                &self.services[idx],
+                Params::default(),
+            ));
+        }
+
+        // the preflight of a redirected route is the one of the route it redirects to
+        if method == Method::Options {
+            let preflight = self.routes.redirects(route).find_map(|(_, idx)| {
+                self.routes
+                    .find(Method::Options, self.services[idx].route())
+            });
+            if let Some((idx, _)) = preflight {
+                return Ok((
+                    Status::Successful(Successful::NoContent),
+                    &self.services[idx],
+                    Params::default(),
+                ));
+            }
+        }
+
+        if !self.routes.allowed(route).is_empty() {
+            return Err(PheasantError::ClientError(ClientError::MethodNotAllowed));
+        }
+
+        Err(PheasantError::ClientError(ClientError::NotFound))
+    }
+
+    // the methods that have a service for the route,
+    // OPTIONS is always allowed when the server answers the preflights
+    fn allowed(&self, route: &str) -> Vec<Method> {
+        let mut methods = self.routes.allowed(route);
+        if self.cors.is_some() && !methods.is_empty() && !methods.contains(&Method::Options) {
+            methods.push(Method::Options);
+            methods.sort();
+        }
+
+        methods
+    }
+
+    // the registered service the new one collides with
+    fn collision(&self, new: &Service) -> Option<RegisterError> {
+        let method = new.method();
+        if let Some(idx) = self.routes.conflict(new) {
+            return Some(RegisterError::conflict(&self.services[idx], new));
+        }
+
+        // a redirect can't be taken by another redirect or be shadowed by a route pattern
+        for route in new.re().into_iter().flatten() {
+            let taken = self
+                .routes
+                .redirect(method, route)
+                .or_else(|| self.routes.find(method, route).map(|(idx, _)| idx));
+            if let Some(idx) = taken {
+                return Some(RegisterError::redirect(route, &self.services[idx], new));
+            }
+        }
+
+        // nor can the new route pattern shadow a registered redirect
+        self.routes
+            .redirected(method)
+            .find(|(route, _)| new.pattern().matches(route).is_some())
+            .map(|(route, idx)| RegisterError::redirect(route, &self.services[idx], new))
+    }
+
+    // hands the error over to the server hook, if there is one
+    fn report(&self, e: PheasantError) {
+        if let Some(hook) = &self.on_error {
+            hook(&e);
+        }
+    }
+
+    fn fail_status(&self, status_code: u16) -> Option<&Failure> {
+        self.errors.iter().find(move |e| e.code() == status_code)
+    }
+
+    pub(crate) async fn error_template(&self, code: u16, proto: Option<Protocol>) -> Response {
         let fail = self.fail_status(code);
         Response::from_err(fail, proto)
             .await
             .unwrap_or(Response::not_implemented().await)
     }
+
+    // renders the error status through its registered failure,
+    // falls back to a bare response of the status if there is none
+    pub(crate) async fn failure(&self, status: ErrorStatus, proto: Option<Protocol>) -> Response {
+        if self.fail_status(status.code()).is_some() {
+            return self.error_template(status.code(), proto).await;
+        }
+
+        let mut resp = Response::failing(status);
+        resp.update_proto(proto.unwrap_or_default());
+
+        resp
+    }
+
+    // runs the tls handshake of a freshly accepted connection
+    // returns None if it fails or takes longer than the keep alive timeout
+    async fn handshake(
+        &self,
+        acceptor: &TlsAcceptor,
+        stream: TcpStream,
+    ) -> Option<(TlsStream<TcpStream>, TlsInfo)> {
+        let stream = tokio::time::timeout(self.keep_alive.timeout, acceptor.accept(stream))
+            .await
+            .ok()?
+            .ok()?;
+        let info = TlsInfo::new(stream.get_ref().1);
+
+        Some((stream, info))
+    }
+
+    // serves the connection if it got a slot, refuses it otherwise
+    async fn connection<S>(
+        self: &Arc<Self>,
+        stream: S,
+        tls: Option<TlsInfo>,
+        permit: Option<OwnedSemaphorePermit>,
+    ) -> PheasantResult<()>
+    where
+        S: Io + 'static,
+    {
+        match permit {
+            // the slot is freed when the permit drops at the end of the task
+            Some(_permit) => self.handle_stream(stream, tls).await,
+            None => self.refuse(stream).await,
+        }
+    }
+
+    // handles a connection stream
+    // keeps serving requests from the same stream as long as the connection persists
+    async fn handle_stream<S>(
+        self: &Arc<Self>,
+        stream: S,
+        tls: Option<TlsInfo>,
+    ) -> PheasantResult<()>
+    where
+        S: Io + 'static,
+    {
+        if tls.as_ref().and_then(|tls| tls.alpn()) == Some("h2") {
+            return http2::serve(self, stream, tls).await;
+        }
+
+        let timeout = self.keep_alive.timeout;
+        let mut reader = BufReader::new(stream);
+        let mut served = 0;
+
+        loop {
+            // the client closed the connection or left it idle past the timeout
+            // idle connections are also closed once the server starts shutting down
+            let idle = async {
+                if served > 0 {
+                    self.shutdown.triggered().await
+                } else {
+                    std::future::pending().await
+                }
+            };
+            tokio::select! {
+                read = tokio::time::timeout(timeout, reader.fill_buf()) => match read {
+                    // a client with prior knowledge of http/2 starts with its preface
+                    Ok(Ok(buf)) if served == 0 && http2::is_preface(buf) => {
+                        return http2::serve(self, reader, tls).await;
+                    }
+                    Ok(Ok(buf)) if !buf.is_empty() => (),
+                    _ => break,
+                },
+                _ = idle => break,
+            }
+            served += 1;
+
+            // the idle timeout only covers the wait for the request to start,
+            // past that it bounds every read so a slow but steady upload isn't cut off
+            let mut stalling = Stalling::new(&mut reader, timeout);
+            let req = Request::from_stream(&mut stalling).await;
+            let stalled = stalling.stalled;
+            let mut req = match req {
+                Ok(req) => req,
+                Err(err) => {
+                    let status = match err {
+                        _ if stalled => ClientError::RequestTimeout,
+                        PheasantError::ClientError(status) => status,
+                        _ => ClientError::BadRequest,
+                    };
+                    let mut resp = self.failure(ErrorStatus::Client(status), None).await;
+                    // the stream can't be trusted to be at the start of a request anymore
+                    self.update_connection(&mut resp, false);
+                    send_response(reader.get_mut(), resp).await?;
+
+                    break;
+                }
+            };
+            req.set_tls(tls.clone());
+            let persist = req.keep_alive()
+                && served < self.keep_alive.max
+                && !self.shutdown.is_shutting_down();
+
+            let mut resp = self.respond(req).await;
+            // the connection is handed over to the upgraded protocol once the 101 is sent
+            // buffered bytes the client sent past the request stay in the reader
+            if let Some(upgrade) = resp.take_upgrade() {
+                send_response(reader.get_mut(), resp).await?;
+
+                upgrade.run(Box::new(reader)).await;
+
+                return Ok(());
+            }
+            // an unsized body can only end with the connection on http 1.0
+            let persist = persist && resp.is_delimited();
+            self.update_connection(&mut resp, persist);
+            send_response(reader.get_mut(), resp).await?;
+
+            if !persist {
+                break;
+            }
+        }
+        // a tls stream sends its close_notify alert on shutdown,
+        // clients would otherwise see the close as a truncation
+        _ = reader.get_mut().shutdown().await;
+
+        Ok(())
+    }
+
+    // runs the request through the server middleware and dispatches it to its service
+    pub(crate) async fn respond(&self, mut req: Request) -> Response {
+        let method = req.method();
+        let mut resp = layered(&self.middleware, &mut req, async |req| {
+            self.dispatch(req).await
+        })
+        .await;
+        if method == Method::Head {
+            resp.strip_body();
+        }
+
+        resp
+    }
+
+    // dispatches the request to its service, through the service middleware
+    async fn dispatch(&self, req: &mut Request) -> Response {
+        let method = req.method();
+        if req.targets_server() {
+            return self.server_options(req);
+        }
+
+        match self.service_status(method, req.route()) {
+            Ok((status, service, params)) => {
+                req.set_path_params(params);
+                // the server default applies to services without a cors policy of their own
+                let default = self.cors.as_ref().filter(|_| service.cors().is_none());
+
+                layered(service.middleware(), req, async |req| {
+                    let mut resp = Response::payload(req, status, service).await;
+                    if let Some(cors) = default {
+                        resp.apply_cors(cors, req.header::<Origin>("Origin").as_ref());
+                    }
+
+                    resp
+                })
+                .await
+            }
+            Err(PheasantError::ClientError(ClientError::NotFound)) => {
+                self.error_template(404, Some(req.proto())).await
+            }
+            Err(PheasantError::ClientError(ClientError::MethodNotAllowed)) => {
+                let preflight = match method {
+                    Method::Options => self.preflight(req),
+                    _ => None,
+                };
+                match preflight {
+                    Some(resp) => resp,
+                    None => self.not_allowed(req).await,
+                }
+            }
+            _ => unimplemented!("not implemented yet"),
+        }
+    }
+
+    // answers a request whose route has no service for its method,
+    // the `Allow` header lists the methods that do
+    async fn not_allowed(&self, req: &Request) -> Response {
+        let status = ErrorStatus::Client(ClientError::MethodNotAllowed);
+        let mut resp = self.failure(status, Some(req.proto())).await;
+        resp.set_header("Allow", allow(&self.allowed(req.route())));
+
+        resp
+    }
+
+    // answers the preflight of a route that has no OPTIONS service,
+    // with the cors policy of the service the actual request would go to or the server default
+    //
+    // returns None if neither has one
+    fn preflight(&self, req: &Request) -> Option<Response> {
+        let cors = req
+            .header::<String>("Access-Control-Request-Method")
+            .and_then(|method| method.parse::<Method>().ok())
+            .and_then(|method| self.service_status(method, req.route()).ok())
+            .and_then(|(_, service, _)| service.cors())
+            .or(self.cors.as_ref())?;
+        let origin = req
+            .header::<Origin>("Origin")
+            .filter(|origin| cors.allows_origin(origin));
+
+        let mut resp = Response::preflight(cors, origin.as_ref());
+        resp.update_status(Status::Successful(Successful::NoContent), None, None)
+            .update_proto(req.proto());
+
+        Some(resp)
+    }
+
+    // answers `OPTIONS *` with the capabilities of the server as a whole,
+    // the methods it has services for and its default cors policy
+    fn server_options(&self, req: &Request) -> Response {
+        let mut methods = self.routes.methods();
+        if !methods.contains(&Method::Options) {
+            methods.push(Method::Options);
+            methods.sort();
+        }
+
+        let mut resp = Response::with_status(200);
+        resp.update_proto(req.proto())
+            .set_header("Allow", allow(&methods));
+        if let Some(cors) = &self.cors {
+            resp.apply_cors(cors, req.header::<Origin>("Origin").as_ref());
+        }
+
+        resp
+    }
+
+    // answers a connection over the server's limit with a 503 and closes it
+    async fn refuse<S>(&self, mut stream: S) -> PheasantResult<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let status = ErrorStatus::Server(ServerError::ServiceUnavailable);
+        let mut resp = self.failure(status, None).await;
+        self.update_connection(&mut resp, false);
+        send_response(&mut stream, resp).await?;
+
+        // closing with the request still unread resets the connection,
+        // the client could lose the response before reading it
+        // so the request is drained until the client closes its side or the timeout
+        _ = stream.shutdown().await;
+        let mut buf = [0; 1024];
+        _ = tokio::time::timeout(self.keep_alive.timeout, async {
+            while let Ok(1..) = stream.read(&mut buf).await {}
+        })
+        .await;
+
+        Ok(())
+    }
+
+    // tells the client wether the connection stays open after this response
+    fn update_connection(&self, resp: &mut Response, persist: bool) {
+        if persist {
+            resp.set_header::<String>("Connection", "keep-alive".into())
+                .set_header("Keep-Alive", self.keep_alive.header());
+        } else {
+            resp.set_header::<String>("Connection", "close".into());
+        }
+    }
+}
+
+// HEAD requests are answered by the GET services
+// sorts and dedups the methods on the way
+fn with_head(mut methods: Vec<Method>) -> Vec<Method> {
+    if methods.contains(&Method::Get) {
+        methods.push(Method::Head);
+    }
+    methods.sort();
+    methods.dedup();
+
+    methods
+}
+
+// the `Allow` header value of the methods
+fn allow(methods: &[Method]) -> String {
+    methods
+        .iter()
+        .map(Method::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// the collision between two services of the same bundle
+fn collision(registered: &Service, new: &Service) -> Option<RegisterError> {
+    if registered.method() != new.method() {
+        return None;
+    }
+    if registered.pattern().conflicts_with(new.pattern()) {
+        return Some(RegisterError::conflict(registered, new));
+    }
+
+    let taken = |by: &Service, route: &Route| {
+        by.redirects_to(route) || by.pattern().matches(route).is_some()
+    };
+    new.re()
+        .into_iter()
+        .flatten()
+        .find(|route| taken(registered, route))
+        .or_else(|| {
+            registered
+                .re()
+                .into_iter()
+                .flatten()
+                .find(|route| new.pattern().matches(route).is_some())
+        })
+        .map(|route| RegisterError::redirect(route, registered, new))
+}
+
+// sends the response to the client
+async fn send_response<W>(stream: &mut W, resp: Response) -> PheasantResult<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let chunked = resp.is_chunked();
+    let (payload, body) = resp.respond();
+
+    stream.write_all(&payload).await?;
+    if let Some(body) = body {
+        body.write_to(stream, chunked).await?;
+    }
+    stream.flush().await?;
+
+    Ok(())
+}
+
+// a reader that fails once a single read waits longer than its timeout
+// the deadline moves forward every time the inner reader makes progress
+struct Stalling<'a, R> {
+    reader: &'a mut R,
+    timeout: Duration,
+    deadline: Pin<Box<Sleep>>,
+    // whether the reader failed because it stalled
+    stalled: bool,
+}
+
+impl<'a, R> Stalling<'a, R> {
+    fn new(reader: &'a mut R, timeout: Duration) -> Self {
+        Self {
+            reader,
+            timeout,
+            deadline: Box::pin(tokio::time::sleep(timeout)),
+            stalled: false,
+        }
+    }
+
+    // resets the deadline on progress, errors out if it passed while the read is pending
+    fn poll_deadline<T>(
+        deadline: &mut Pin<Box<Sleep>>,
+        timeout: Duration,
+        stalled: &mut bool,
+        cx: &mut Context<'_>,
+        read: Poll<io::Result<T>>,
+    ) -> Poll<io::Result<T>> {
+        if read.is_ready() {
+            deadline
+                .as_mut()
+                .reset(tokio::time::Instant::now() + timeout);
+
+            return read;
+        }
+
+        match deadline.as_mut().poll(cx) {
+            Poll::Ready(()) => {
+                *stalled = true;
+
+                Poll::Ready(Err(io::ErrorKind::TimedOut.into()))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
 }
 
-// sends the response to the client and returns the connection tcp stream
-fn send_response(mut stream: TcpStream, resp: Response) -> PheasantResult<TcpStream> {
-    let payload = resp.respond();
+impl<R> AsyncRead for Stalling<'_, R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let read = Pin::new(&mut *this.reader).poll_read(cx, buf);
 
-    stream.write_all(&payload)?;
-    stream.flush()?;
+        Self::poll_deadline(
+            &mut this.deadline,
+            this.timeout,
+            &mut this.stalled,
+            cx,
+            read,
+        )
+    }
+}
+
+impl<R> AsyncBufRead for Stalling<'_, R>
+where
+    R: AsyncBufRead + Unpin,
+{
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        let read = Pin::new(&mut *this.reader).poll_fill_buf(cx);
 
-    Ok(stream)
+        Self::poll_deadline(
+            &mut this.deadline,
+            this.timeout,
+            &mut this.stalled,
+            cx,
+            read,
+        )
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        Pin::new(&mut *this.reader).consume(amt)
+    }
 }
 
 // #[deprecated(note = "replaced by Request::from_stream")]
@@ -1,18 +1,26 @@
 use std::collections::HashSet;
 use std::pin::Pin;
+use std::sync::Arc;
 
-use crate::{Cors, Method, Mime, Protocol, Request, Response};
-use pheasant_uri::Route;
+use crate::middleware::Layers;
+use crate::{Cors, Method, Middleware, Mime, Protocol, Request, Response};
+use pheasant_uri::{Pattern, Route};
 
 /// a http server service type
 /// contains the logic that gets executed when a request is made
 pub struct Service {
     method: Method,
     route: Route,
+    // the route parsed as a pattern, which may capture path parameters
+    pattern: Pattern,
     redirects: Option<HashSet<Route>>,
     mime: Option<Mime>,
     service: BoxFun,
     cors: Option<Cors>,
+    // the layers wrapping the service, those of its scopes first
+    middleware: Layers,
+    // the type name of the handler function, to tell services apart in registration errors
+    name: &'static str,
 }
 
 unsafe impl Send for Service {}
@@ -65,10 +73,13 @@ impl Service {
     {
         Self {
             method,
+            pattern: Pattern::from(&route),
             route,
             mime,
             cors,
             redirects,
+            middleware: vec![],
+            name: std::any::type_name::<F>(),
             service: Box::new(move |req: &Request| {
                 let proto = req.proto();
 
@@ -95,6 +106,16 @@ impl Service {
         &self.route
     }
 
+    /// returns the name of the handler function, e.g., "app::user"
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// returns the route pattern the request paths are matched against
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
     pub fn re(&self) -> Option<&HashSet<Route>> {
         self.redirects.as_ref()
     }
@@ -114,6 +135,59 @@ impl Service {
         re.iter().find(|r| r.as_str() == route).is_some()
     }
 
+    // prepends the prefix of the scope the service is in to its route and redirects
+    pub(crate) fn nest(&mut self, prefix: &str) {
+        let nest = |route: &mut Route| match route.as_str() {
+            "/" if !prefix.is_empty() => {
+                route.clear();
+                route.push_str(prefix);
+            }
+            _ => route.insert_str(0, prefix),
+        };
+
+        nest(&mut self.route);
+        self.pattern = Pattern::from(&self.route);
+        if let Some(redirects) = self.redirects.take() {
+            self.redirects = Some(
+                redirects
+                    .into_iter()
+                    .map(|mut route| {
+                        nest(&mut route);
+                        route
+                    })
+                    .collect(),
+            );
+        }
+    }
+
+    /// wraps the service in a middleware layer, see `Middleware`
+    ///
+    /// the layers of a service run after those of the server and of its scopes
+    pub fn wrap(&mut self, middleware: impl Middleware) -> &mut Self {
+        self.middleware.push(Arc::new(middleware));
+
+        self
+    }
+
+    pub(crate) fn middleware(&self) -> &[Arc<dyn Middleware>] {
+        &self.middleware
+    }
+
+    // wraps the service in the layers of the scope it is in, outside of its own
+    pub(crate) fn wrap_outer(&mut self, layers: &[Arc<dyn Middleware>]) {
+        self.middleware.splice(0..0, layers.iter().cloned());
+    }
+
+    // sets the cors policy unless the service has one
+    pub(crate) fn default_cors(&mut self, cors: &Cors) {
+        self.cors.get_or_insert_with(|| cors.clone());
+    }
+
+    // sets the mime type unless the service has one
+    pub(crate) fn default_mime(&mut self, mime: &Mime) {
+        self.mime.get_or_insert_with(|| mime.clone());
+    }
+
     pub(crate) fn cors(&self) -> Option<&Cors> {
         self.cors.as_ref()
     }
@@ -0,0 +1,221 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_core::Stream;
+use tokio::sync::mpsc;
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+
+use crate::{BodyStream, HeaderMap, Mime, Response};
+
+/// a server-sent event
+///
+/// ```
+/// # use pheasant_core::Event;
+/// let event = Event::data("{ \"cpu\": 12 }").event("stats").id("42");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Event {
+    event: Option<String>,
+    data: String,
+    id: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl Event {
+    /// creates a new event carrying `data`
+    ///
+    /// multi-line data is sent as one `data:` field per line
+    pub fn data(data: impl Into<String>) -> Self {
+        Self {
+            data: data.into(),
+            ..Default::default()
+        }
+    }
+
+    /// sets the event type, the client dispatches the event to the listeners of that type
+    pub fn event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(single_line(event.into()));
+
+        self
+    }
+
+    /// sets the event id, the client sends it back as `Last-Event-ID` when it reconnects
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(single_line(id.into()));
+
+        self
+    }
+
+    /// sets how long the client waits before reconnecting once the stream is lost
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+
+        self
+    }
+
+    // formats the event into a text/event-stream frame
+    fn frame(&self) -> Vec<u8> {
+        let mut frame = String::new();
+        if let Some(ref event) = self.event {
+            frame.push_str("event: ");
+            frame.push_str(event);
+            frame.push('\n');
+        }
+        // an empty line still makes a data field, empty data included
+        for line in self.data.split('\n') {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            frame.push_str("data: ");
+            frame.push_str(line);
+            frame.push('\n');
+        }
+        if let Some(ref id) = self.id {
+            frame.push_str("id: ");
+            frame.push_str(id);
+            frame.push('\n');
+        }
+        if let Some(retry) = self.retry {
+            frame.push_str(&format!("retry: {}\n", retry.as_millis()));
+        }
+        frame.push('\n');
+
+        frame.into_bytes()
+    }
+}
+
+// event and id fields can't span more than one line
+fn single_line(mut s: String) -> String {
+    s.retain(|c| c != '\n' && c != '\r');
+
+    s
+}
+
+/// a text/event-stream response
+///
+/// the connection stays open and every event from the source is written to it as it comes,
+/// the stream ends when the source is exhausted or when the client disconnects,
+/// in which case the sending half of the channel gets closed
+///
+/// ```ignore
+/// #[get("/feed")]
+/// async fn feed(_: ()) -> Response {
+///     let (tx, sse) = Sse::channel(16);
+///     tokio::spawn(async move {
+///         while tx.send(Event::data("tick")).await.is_ok() {
+///             tokio::time::sleep(Duration::from_secs(1)).await;
+///         }
+///     });
+///
+///     sse.into()
+/// }
+/// ```
+pub struct Sse {
+    events: Events,
+    heartbeat: Option<Duration>,
+}
+
+enum Events {
+    Channel(mpsc::Receiver<Event>),
+    Stream(Pin<Box<dyn Stream<Item = Event> + Send>>),
+}
+
+impl Sse {
+    /// makes an event stream out of the receiving half of a channel
+    pub fn new(rx: mpsc::Receiver<Event>) -> Self {
+        Self {
+            events: Events::Channel(rx),
+            heartbeat: Some(Duration::from_secs(15)),
+        }
+    }
+
+    /// makes a channel with room for `buffer` events
+    /// and an event stream out of its receiving half
+    pub fn channel(buffer: usize) -> (mpsc::Sender<Event>, Self) {
+        let (tx, rx) = mpsc::channel(buffer);
+
+        (tx, Self::new(rx))
+    }
+
+    /// makes an event stream out of a stream of events
+    pub fn from_stream<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Event> + Send + 'static,
+    {
+        Self {
+            events: Events::Stream(Box::pin(stream)),
+            heartbeat: Some(Duration::from_secs(15)),
+        }
+    }
+
+    /// sets how often a heartbeat comment is sent while no events are sent
+    ///
+    /// heartbeats keep proxies from timing the connection out
+    /// and detect clients that went away; defaults to 15 seconds
+    pub fn heartbeat(mut self, every: Duration) -> Self {
+        self.heartbeat = Some(every);
+
+        self
+    }
+
+    /// turns the heartbeats off
+    pub fn no_heartbeat(mut self) -> Self {
+        self.heartbeat = None;
+
+        self
+    }
+}
+
+impl From<Sse> for Response {
+    fn from(sse: Sse) -> Self {
+        let heartbeat = sse.heartbeat.map(|every| {
+            let mut interval = tokio::time::interval_at(Instant::now() + every, every);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            interval
+        });
+
+        let mut resp = Response::default();
+        resp.set_header("Content-Type", Mime::macro_checked("text/event-stream"))
+            .set_header::<String>("Cache-Control", "no-cache".into())
+            .update_body(BodyStream::from_stream(Frames {
+                events: sse.events,
+                heartbeat,
+            }));
+
+        resp
+    }
+}
+
+// the frames written to the response body,
+// the events as they come, with heartbeats in between
+struct Frames {
+    events: Events,
+    heartbeat: Option<Interval>,
+}
+
+impl Stream for Frames {
+    type Item = Vec<u8>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let event = match self.events {
+            Events::Channel(ref mut rx) => rx.poll_recv(cx),
+            Events::Stream(ref mut stream) => stream.as_mut().poll_next(cx),
+        };
+        match event {
+            Poll::Ready(event) => {
+                // the next heartbeat is due a whole period after the last event
+                if let Some(ref mut interval) = self.heartbeat {
+                    interval.reset();
+                }
+
+                return Poll::Ready(event.map(|ev| ev.frame()));
+            }
+            Poll::Pending => (),
+        }
+
+        match self.heartbeat {
+            Some(ref mut interval) => interval.poll_tick(cx).map(|_| Some(b":\n\n".to_vec())),
+            None => Poll::Pending,
+        }
+    }
+}
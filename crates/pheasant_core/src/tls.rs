@@ -1,34 +1,221 @@
-use pheasant_uri::Origin;
-use rcgen::{Certificate, CertifiedKey, KeyPair, generate_simple_self_signed};
+use chrono::{Datelike, NaiveDate, Utc};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, CertifiedKey, DnType,
+    ExtendedKeyUsagePurpose, IsCa, Issuer, KeyPair, KeyUsagePurpose, PKCS_ECDSA_P256_SHA256,
+    PKCS_ECDSA_P384_SHA384, PKCS_ED25519, date_time_ymd, generate_simple_self_signed,
+};
 use rustls::crypto::ring::default_provider as ring_provider;
+use rustls::server::danger::ClientCertVerifier;
+use rustls::server::{
+    ClientHello, ResolvesServerCert, VerifierBuilderError, WantsServerCert, WebPkiClientVerifier,
+};
+use rustls::sign;
 use rustls::{
-    ConfigBuilder, ProtocolVersion, ServerConfig, ServerConnection, SupportedProtocolVersion,
+    CipherSuite, ConfigBuilder, ProtocolVersion, RootCertStore, ServerConfig, ServerConnection,
 };
+use rustls_pki_types::pem::{self, PemObject};
 use rustls_pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use std::collections::HashMap;
+use std::fmt;
 use std::fs;
-use std::net::TcpStream;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use crate::Request;
 
-pub fn make_cert(subject_alt_names: &[String]) -> Result<(), rcgen::Error> {
+/// tls setup error type
+#[derive(Debug)]
+pub enum TlsError {
+    /// a pem file could not be read or its content could not be parsed
+    Pem(pem::Error),
+    /// the pem input holds no certificate
+    NoCertificates,
+    /// rustls rejected the certificate chain or the private key
+    Rustls(rustls::Error),
+    /// a certificate could not be generated or a ca could not be loaded
+    Rcgen(rcgen::Error),
+    /// an unknown key type name
+    KeyType(String),
+    /// a certificate was registered under an invalid server name
+    ServerName(String),
+    /// the client certificate verifier could not be built from the ca bundle
+    Verifier(VerifierBuilderError),
+    /// the generated certificate could not be written to disk
+    Io(std::io::Error),
+}
+
+impl fmt::Display for TlsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pem(e) => write!(f, "bad pem input: {}", e),
+            Self::NoCertificates => write!(f, "no certificate found in the pem input"),
+            Self::Rustls(e) => write!(f, "rejected by rustls: {}", e),
+            Self::Rcgen(e) => write!(f, "certificate generation failed: {}", e),
+            Self::KeyType(name) => write!(f, "unknown key type: {:?}", name),
+            Self::ServerName(name) => write!(f, "invalid server name: {:?}", name),
+            Self::Verifier(e) => write!(f, "bad client ca bundle: {}", e),
+            Self::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for TlsError {}
+
+impl From<pem::Error> for TlsError {
+    fn from(err: pem::Error) -> Self {
+        Self::Pem(err)
+    }
+}
+
+impl From<rustls::Error> for TlsError {
+    fn from(err: rustls::Error) -> Self {
+        Self::Rustls(err)
+    }
+}
+
+impl From<rcgen::Error> for TlsError {
+    fn from(err: rcgen::Error) -> Self {
+        Self::Rcgen(err)
+    }
+}
+
+impl From<VerifierBuilderError> for TlsError {
+    fn from(err: VerifierBuilderError) -> Self {
+        Self::Verifier(err)
+    }
+}
+
+impl From<std::io::Error> for TlsError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// generates a self signed certificate and writes it along with its key
+/// to `tls/cert.pem` and `tls/key.pem`
+///
+/// they can be loaded back with `config_from_pem_files`
+pub fn make_cert(subject_alt_names: &[String]) -> Result<(), TlsError> {
     let (cert, key) = generate_certificate(subject_alt_names)?;
     let cert_pem = cert.pem();
     let key_pem = key.serialize_pem();
-    fs::write("tls/cert.pem", cert_pem.into_bytes()).unwrap();
-    fs::write("tls/key.pem", key_pem.into_bytes()).unwrap();
+    fs::write("tls/cert.pem", cert_pem.into_bytes())?;
+    fs::write("tls/key.pem", key_pem.into_bytes())?;
 
     Ok(())
 }
 
-pub fn tls_conn(subject_alt_names: &[String]) -> Result<ServerConnection, rcgen::Error> {
+pub fn tls_conn(subject_alt_names: &[String]) -> Result<ServerConnection, TlsError> {
+    let config = self_signed_config(subject_alt_names)?;
+
+    Ok(ServerConnection::new(Arc::new(config))?)
+}
+
+/// generates a server config with a fresh self signed certificate,
+/// meant for local development
+///
+/// ```no_run
+/// # use pheasant_core::{Server, self_signed_config};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let mut server = Server::new([127, 0, 0, 1], 8443, 64)?;
+/// server.tls(self_signed_config(&["localhost".into()])?);
+/// # Ok(())
+/// # }
+/// ```
+pub fn self_signed_config(subject_alt_names: &[String]) -> Result<ServerConfig, TlsError> {
     let (cert, key) = generate_certificate(subject_alt_names)?;
     let cert_der = cert.der().clone();
     let key_der: PrivatePkcs8KeyDer<'_> =
         <Vec<u8> as Into<PrivatePkcs8KeyDer<'_>>>::into(key.serialize_der());
     let key_der: PrivateKeyDer<'_> = key_der.into();
 
-    let config = generate_configs(vec![cert_der], key_der);
+    generate_configs(vec![cert_der], key_der, &ClientAuth::None)
+}
+
+/// builds a server config out of a pem certificate chain file and a pem private key file
+///
+/// ```no_run
+/// # use pheasant_core::{Server, config_from_pem_files};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let mut server = Server::new([127, 0, 0, 1], 8443, 64)?;
+/// server.tls(config_from_pem_files("tls/fullchain.pem", "tls/key.pem")?);
+/// # Ok(())
+/// # }
+/// ```
+pub fn config_from_pem_files(
+    cert: impl AsRef<Path>,
+    key: impl AsRef<Path>,
+) -> Result<ServerConfig, TlsError> {
+    generate_configs(load_certs(cert)?, load_key(key)?, &ClientAuth::None)
+}
 
-    Ok(ServerConnection::new(Arc::new(config)).unwrap())
+/// builds a server config out of a pem certificate chain and a pem private key
+pub fn config_from_pem(cert: &[u8], key: &[u8]) -> Result<ServerConfig, TlsError> {
+    generate_configs(parse_certs(cert)?, parse_key(key)?, &ClientAuth::None)
+}
+
+/// builds a server config out of a certificate chain and a private key
+/// that authenticates clients through their certificates (mutual tls)
+///
+/// ```no_run
+/// # use pheasant_core::{ClientAuth, config_with_client_auth, load_certs, load_key};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let auth = ClientAuth::required("tls/clients-ca.pem")?;
+/// let config = config_with_client_auth(
+///     load_certs("tls/fullchain.pem")?,
+///     load_key("tls/key.pem")?,
+///     &auth,
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn config_with_client_auth(
+    cert: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+    auth: &ClientAuth,
+) -> Result<ServerConfig, TlsError> {
+    generate_configs(cert, key, auth)
+}
+
+/// reads a certificate chain from a pem file
+///
+/// the chain starts with the end entity certificate, followed by its issuers
+pub fn load_certs(path: impl AsRef<Path>) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let certs = CertificateDer::pem_file_iter(path)?.collect::<Result<Vec<_>, _>>()?;
+
+    non_empty(certs)
+}
+
+/// parses a certificate chain out of pem bytes
+pub fn parse_certs(pem: &[u8]) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let certs = CertificateDer::pem_slice_iter(pem).collect::<Result<Vec<_>, _>>()?;
+
+    non_empty(certs)
+}
+
+fn non_empty(
+    certs: Vec<CertificateDer<'static>>,
+) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    if certs.is_empty() {
+        return Err(TlsError::NoCertificates);
+    }
+
+    Ok(certs)
+}
+
+/// reads the first private key of a pem file,
+/// the key can be a pkcs#8, pkcs#1 (rsa) or sec1 (ec) key
+pub fn load_key(path: impl AsRef<Path>) -> Result<PrivateKeyDer<'static>, TlsError> {
+    Ok(PrivateKeyDer::from_pem_file(path)?)
+}
+
+/// parses the first private key out of pem bytes,
+/// the key can be a pkcs#8, pkcs#1 (rsa) or sec1 (ec) key
+pub fn parse_key(pem: &[u8]) -> Result<PrivateKeyDer<'static>, TlsError> {
+    Ok(PrivateKeyDer::from_pem_slice(pem)?)
 }
 
 fn generate_certificate(
@@ -42,11 +229,747 @@ fn generate_certificate(
 fn generate_configs(
     cert: Vec<CertificateDer<'static>>,
     private_key: PrivateKeyDer<'static>,
-) -> ServerConfig {
-    ServerConfig::builder_with_provider(Arc::new(ring_provider()))
-        .with_safe_default_protocol_versions()
-        .unwrap()
-        .with_no_client_auth()
-        .with_single_cert(cert, private_key)
-        .unwrap()
+    auth: &ClientAuth,
+) -> Result<ServerConfig, TlsError> {
+    let config = config_builder(auth)?.with_single_cert(cert, private_key)?;
+
+    Ok(with_alpn(config))
+}
+
+fn config_builder(
+    auth: &ClientAuth,
+) -> Result<ConfigBuilder<ServerConfig, WantsServerCert>, TlsError> {
+    let builder = ServerConfig::builder_with_provider(Arc::new(ring_provider()))
+        .with_safe_default_protocol_versions()?;
+
+    Ok(match auth.verifier()? {
+        Some(verifier) => builder.with_client_cert_verifier(verifier),
+        None => builder.with_no_client_auth(),
+    })
+}
+
+/// the key algorithm of the certificates a `DevCa` generates
+///
+/// rsa keys are left out, the ring backend can't generate them
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    #[default]
+    EcdsaP256,
+    EcdsaP384,
+    Ed25519,
+}
+
+impl KeyType {
+    fn generate(self) -> Result<KeyPair, TlsError> {
+        let alg = match self {
+            Self::EcdsaP256 => &PKCS_ECDSA_P256_SHA256,
+            Self::EcdsaP384 => &PKCS_ECDSA_P384_SHA384,
+            Self::Ed25519 => &PKCS_ED25519,
+        };
+
+        Ok(KeyPair::generate_for(alg)?)
+    }
+}
+
+impl std::str::FromStr for KeyType {
+    type Err = TlsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "p256" | "ecdsa-p256" => Ok(Self::EcdsaP256),
+            "p384" | "ecdsa-p384" => Ok(Self::EcdsaP384),
+            "ed25519" => Ok(Self::Ed25519),
+            _ => Err(TlsError::KeyType(s.to_owned())),
+        }
+    }
+}
+
+/// a local certificate authority for development and tests
+///
+/// the leaf certificates it issues are verified by any client
+/// that trusts the ca certificate, unlike the ones of `self_signed_config`
+///
+/// ```no_run
+/// # use pheasant_core::{DevCa, KeyType, Server};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let mut server = Server::new([127, 0, 0, 1], 8443, 64)?;
+/// let ca = DevCa::generate("pheasant dev ca", KeyType::default(), 3650)?;
+/// ca.write("tls")?;
+///
+/// let cert = ca.issue(&["localhost".into()], KeyType::default(), 90)?;
+/// server.tls(cert.config()?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct DevCa {
+    cert: CertificateDer<'static>,
+    cert_pem: String,
+    issuer: Issuer<'static, KeyPair>,
+}
+
+impl DevCa {
+    /// generates a new ca certificate and key, valid for `validity_days` from today
+    pub fn generate(name: &str, key_type: KeyType, validity_days: u32) -> Result<Self, TlsError> {
+        let key = key_type.generate()?;
+        let mut params = ca_params(name);
+        validity(&mut params, validity_days);
+        let cert = params.self_signed(&key)?;
+
+        Ok(Self {
+            cert: cert.der().clone(),
+            cert_pem: cert.pem(),
+            issuer: Issuer::new(params, key),
+        })
+    }
+
+    /// loads back a ca from the `ca.pem` and `ca.key` files `DevCa::write` put in `dir`
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, TlsError> {
+        let dir = dir.as_ref();
+        let cert_pem = fs::read_to_string(dir.join("ca.pem"))?;
+        let key = KeyPair::from_pem(&fs::read_to_string(dir.join("ca.key"))?)?;
+        let cert = parse_certs(cert_pem.as_bytes())?.remove(0);
+
+        // the issued certificates name their issuer after the ca subject
+        let (_, x509) = X509Certificate::from_der(&cert)
+            .map_err(|_| TlsError::Rcgen(rcgen::Error::CouldNotParseCertificate))?;
+        let name = x509
+            .subject()
+            .iter_common_name()
+            .next()
+            .and_then(|cn| cn.as_str().ok())
+            .ok_or(TlsError::Rcgen(rcgen::Error::CouldNotParseCertificate))?
+            .to_owned();
+
+        Ok(Self {
+            cert,
+            cert_pem,
+            issuer: Issuer::new(ca_params(&name), key),
+        })
+    }
+
+    /// the ca certificate, to be added to the trust store of clients
+    pub fn cert(&self) -> &CertificateDer<'static> {
+        &self.cert
+    }
+
+    /// the ca certificate in pem format
+    pub fn cert_pem(&self) -> &str {
+        &self.cert_pem
+    }
+
+    /// writes the ca certificate and key to `ca.pem` and `ca.key` in `dir`,
+    /// the directory is created if it doesn't exist
+    ///
+    /// only `ca.pem` is meant to be shared, `ca.key` can sign certificates for any name
+    pub fn write(&self, dir: impl AsRef<Path>) -> Result<(), TlsError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join("ca.pem"), &self.cert_pem)?;
+        write_key(&dir.join("ca.key"), &self.issuer.key().serialize_pem())
+    }
+
+    /// issues a leaf certificate for the subject alternative names (dns names or ip addresses),
+    /// valid for `validity_days` from today
+    pub fn issue(
+        &self,
+        subject_alt_names: &[String],
+        key_type: KeyType,
+        validity_days: u32,
+    ) -> Result<IssuedCert, TlsError> {
+        let key = key_type.generate()?;
+        let mut params = CertificateParams::new(subject_alt_names)?;
+        if let Some(name) = subject_alt_names.first() {
+            params
+                .distinguished_name
+                .push(DnType::CommonName, name.as_str());
+        }
+        params.is_ca = IsCa::ExplicitNoCa;
+        params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+        params.extended_key_usages = vec![
+            ExtendedKeyUsagePurpose::ServerAuth,
+            ExtendedKeyUsagePurpose::ClientAuth,
+        ];
+        params.use_authority_key_identifier_extension = true;
+        validity(&mut params, validity_days);
+        let cert = params.signed_by(&key, &self.issuer)?;
+
+        Ok(IssuedCert {
+            cert: cert.der().clone(),
+            cert_pem: cert.pem(),
+            key,
+        })
+    }
+}
+
+impl fmt::Debug for DevCa {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DevCa")
+            .field("cert_pem", &self.cert_pem)
+            .finish_non_exhaustive()
+    }
+}
+
+/// a leaf certificate issued by a `DevCa` along with its private key
+pub struct IssuedCert {
+    cert: CertificateDer<'static>,
+    cert_pem: String,
+    key: KeyPair,
+}
+
+impl IssuedCert {
+    /// the certificate
+    pub fn cert(&self) -> &CertificateDer<'static> {
+        &self.cert
+    }
+
+    /// the certificate in pem format
+    pub fn cert_pem(&self) -> &str {
+        &self.cert_pem
+    }
+
+    /// the private key in der format
+    pub fn key(&self) -> PrivateKeyDer<'static> {
+        PrivatePkcs8KeyDer::from(self.key.serialize_der()).into()
+    }
+
+    /// the private key in pem format
+    pub fn key_pem(&self) -> String {
+        self.key.serialize_pem()
+    }
+
+    /// writes the certificate and key to `{name}.pem` and `{name}.key` in `dir`,
+    /// the directory is created if it doesn't exist
+    ///
+    /// they can be loaded back with `config_from_pem_files`
+    pub fn write(&self, dir: impl AsRef<Path>, name: &str) -> Result<(), TlsError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(format!("{}.pem", name)), &self.cert_pem)?;
+        write_key(&dir.join(format!("{}.key", name)), &self.key_pem())
+    }
+
+    /// builds a server config that presents this certificate
+    pub fn config(&self) -> Result<ServerConfig, TlsError> {
+        generate_configs(vec![self.cert.clone()], self.key(), &ClientAuth::None)
+    }
+}
+
+impl fmt::Debug for IssuedCert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IssuedCert")
+            .field("cert_pem", &self.cert_pem)
+            .finish_non_exhaustive()
+    }
+}
+
+// the params of a ca certificate that can only sign leaf certificates
+fn ca_params(name: &str) -> CertificateParams {
+    let mut params = CertificateParams::default();
+    params.distinguished_name.push(DnType::CommonName, name);
+    params.is_ca = IsCa::Ca(BasicConstraints::Constrained(0));
+    params.key_usages = vec![
+        KeyUsagePurpose::KeyCertSign,
+        KeyUsagePurpose::CrlSign,
+        KeyUsagePurpose::DigitalSignature,
+    ];
+
+    params
+}
+
+// valid from yesterday, to leave room for clock skew, to `days` from today
+fn validity(params: &mut CertificateParams, days: u32) {
+    let today = Utc::now().date_naive();
+    let date = |date: NaiveDate| date_time_ymd(date.year(), date.month() as u8, date.day() as u8);
+
+    params.not_before = date(today - chrono::Days::new(1));
+    params.not_after = date(today + chrono::Days::new(days as u64));
+}
+
+// private keys are only readable by their owner
+fn write_key(path: &Path, pem: &str) -> Result<(), TlsError> {
+    fs::write(path, pem)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(())
+}
+
+/// client certificate authentication policy (mutual tls)
+///
+/// client certificates are verified against the certificate authorities of the bundle,
+/// services get the verified chain through `ClientCert`
+#[derive(Debug, Clone, Default)]
+pub enum ClientAuth {
+    /// clients are not asked for a certificate
+    #[default]
+    None,
+    /// clients are asked for a certificate but can go without one
+    Optional(Arc<RootCertStore>),
+    /// the handshake fails for clients without a valid certificate
+    Required(Arc<RootCertStore>),
+}
+
+impl ClientAuth {
+    /// verifies the certificates of the clients that send one against a pem ca bundle
+    pub fn optional(ca_bundle: impl AsRef<Path>) -> Result<Self, TlsError> {
+        Ok(Self::Optional(root_store(ca_bundle)?))
+    }
+
+    /// requires every client to send a certificate issued by one of a pem ca bundle's authorities
+    pub fn required(ca_bundle: impl AsRef<Path>) -> Result<Self, TlsError> {
+        Ok(Self::Required(root_store(ca_bundle)?))
+    }
+
+    fn verifier(&self) -> Result<Option<Arc<dyn ClientCertVerifier>>, TlsError> {
+        let (roots, required) = match self {
+            Self::None => return Ok(None),
+            Self::Optional(roots) => (roots, false),
+            Self::Required(roots) => (roots, true),
+        };
+        let builder =
+            WebPkiClientVerifier::builder_with_provider(roots.clone(), Arc::new(ring_provider()));
+        let builder = if required {
+            builder
+        } else {
+            builder.allow_unauthenticated()
+        };
+
+        Ok(Some(builder.build()?))
+    }
+}
+
+fn root_store(ca_bundle: impl AsRef<Path>) -> Result<Arc<RootCertStore>, TlsError> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(ca_bundle)? {
+        roots.add(cert)?;
+    }
+
+    Ok(Arc::new(roots))
+}
+
+// http/2 is preferred when the client supports it
+fn with_alpn(mut config: ServerConfig) -> ServerConfig {
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    config
+}
+
+/// serves each tls connection with the certificate of the server name the client asked for (sni)
+///
+/// a name can be exact, `api.example.com`, or a wildcard, `*.example.com`,
+/// which covers a single label under the domain; exact names win over wildcards,
+/// clients asking for an unknown name or for none get the default certificate
+/// and the handshake fails if there is none
+///
+/// ```no_run
+/// # use pheasant_core::{Server, SniResolver};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let mut server = Server::new([127, 0, 0, 1], 8443, 64)?;
+/// let mut certs = SniResolver::new();
+/// certs
+///     .add_pem_files("example.com", "tls/example.pem", "tls/example.key")?
+///     .add_pem_files("*.example.com", "tls/wildcard.pem", "tls/wildcard.key")?
+///     .default_pem_files("tls/default.pem", "tls/default.key")?;
+///
+/// server.tls(certs.into_config()?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct SniResolver {
+    exact: HashMap<String, Arc<sign::CertifiedKey>>,
+    // keyed on the domain under the wildcard, `*.example.com` -> `example.com`
+    wildcards: HashMap<String, Arc<sign::CertifiedKey>>,
+    default: Option<Arc<sign::CertifiedKey>>,
+}
+
+impl SniResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// registers the certificate chain and key served to clients asking for `name`
+    pub fn add(
+        &mut self,
+        name: &str,
+        cert: Vec<CertificateDer<'static>>,
+        key: PrivateKeyDer<'static>,
+    ) -> Result<&mut Self, TlsError> {
+        let name = name.trim().to_ascii_lowercase();
+        let key = certified_key(cert, key)?;
+
+        match name.strip_prefix("*.") {
+            Some(domain) if is_server_name(domain) => {
+                self.wildcards.insert(domain.to_owned(), key);
+            }
+            None if is_server_name(&name) => {
+                self.exact.insert(name, key);
+            }
+            _ => return Err(TlsError::ServerName(name)),
+        }
+
+        Ok(self)
+    }
+
+    /// registers the pem certificate chain and key files served to clients asking for `name`
+    pub fn add_pem_files(
+        &mut self,
+        name: &str,
+        cert: impl AsRef<Path>,
+        key: impl AsRef<Path>,
+    ) -> Result<&mut Self, TlsError> {
+        self.add(name, load_certs(cert)?, load_key(key)?)
+    }
+
+    /// sets the certificate chain and key served when no registered name matches
+    pub fn default_cert(
+        &mut self,
+        cert: Vec<CertificateDer<'static>>,
+        key: PrivateKeyDer<'static>,
+    ) -> Result<&mut Self, TlsError> {
+        self.default = Some(certified_key(cert, key)?);
+
+        Ok(self)
+    }
+
+    /// sets the pem certificate chain and key files served when no registered name matches
+    pub fn default_pem_files(
+        &mut self,
+        cert: impl AsRef<Path>,
+        key: impl AsRef<Path>,
+    ) -> Result<&mut Self, TlsError> {
+        self.default_cert(load_certs(cert)?, load_key(key)?)
+    }
+
+    /// builds a server config that resolves its certificates through this
+    pub fn into_config(self) -> Result<ServerConfig, TlsError> {
+        self.into_config_with_client_auth(&ClientAuth::None)
+    }
+
+    /// builds a server config that resolves its certificates through this
+    /// and authenticates clients through their certificates
+    pub fn into_config_with_client_auth(self, auth: &ClientAuth) -> Result<ServerConfig, TlsError> {
+        let config = config_builder(auth)?.with_cert_resolver(Arc::new(self));
+
+        Ok(with_alpn(config))
+    }
+
+    fn find(&self, name: &str) -> Option<&Arc<sign::CertifiedKey>> {
+        self.exact.get(name).or_else(|| {
+            name.split_once('.')
+                .and_then(|(_, domain)| self.wildcards.get(domain))
+        })
+    }
+}
+
+impl ResolvesServerCert for SniResolver {
+    fn resolve(&self, hello: ClientHello<'_>) -> Option<Arc<sign::CertifiedKey>> {
+        hello
+            .server_name()
+            .and_then(|name| self.find(&name.to_ascii_lowercase()))
+            .or(self.default.as_ref())
+            .cloned()
+    }
+}
+
+// checks the chain and key go together
+fn certified_key(
+    cert: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+) -> Result<Arc<sign::CertifiedKey>, TlsError> {
+    let cert = non_empty(cert)?;
+
+    Ok(Arc::new(sign::CertifiedKey::from_der(
+        cert,
+        key,
+        &ring_provider(),
+    )?))
+}
+
+// dot separated, non empty labels made of letters, digits and hyphens
+fn is_server_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+// the function a reloadable config is (re)built with
+type LoadConfig = dyn Fn() -> Result<ServerConfig, TlsError> + Send + Sync;
+
+/// a tls config that can be reloaded while the server runs
+///
+/// a reload builds a whole new config and swaps it in at once,
+/// connections that already went through their handshake keep the old one
+/// while new handshakes get the new one;
+/// if the new config can't be built, the old one stays in use
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use pheasant_core::{ReloadableTls, Server};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let mut server = Server::new([127, 0, 0, 1], 8443, 64)?;
+/// let tls = ReloadableTls::from_pem_files("tls/fullchain.pem", "tls/key.pem")?;
+/// // on SIGHUP
+/// tls.reload_on_signal(|res| {
+///     if let Err(e) = res {
+///         eprintln!("tls config reload failed, keeping the old one: {}", e);
+///     }
+/// });
+/// // whenever certbot touches the files
+/// tls.watch(Duration::from_secs(60), |_| ());
+///
+/// server.tls(tls.clone());
+/// // or on demand
+/// tls.reload()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ReloadableTls {
+    current: Arc<RwLock<Arc<ServerConfig>>>,
+    // None for configs that can't be reloaded
+    load: Option<Arc<LoadConfig>>,
+    // the files the config is built from
+    files: Arc<Vec<PathBuf>>,
+}
+
+impl ReloadableTls {
+    /// builds the config with `load`, which is called again on every reload
+    ///
+    /// `files` are the files `load` reads, `watch` looks for changes in them
+    pub fn new<F>(files: Vec<PathBuf>, load: F) -> Result<Self, TlsError>
+    where
+        F: Fn() -> Result<ServerConfig, TlsError> + Send + Sync + 'static,
+    {
+        Ok(Self {
+            current: Arc::new(RwLock::new(Arc::new(load()?))),
+            load: Some(Arc::new(load)),
+            files: Arc::new(files),
+        })
+    }
+
+    /// builds the config from a pem certificate chain file and a pem private key file,
+    /// reloads read them again
+    pub fn from_pem_files(
+        cert: impl Into<PathBuf>,
+        key: impl Into<PathBuf>,
+    ) -> Result<Self, TlsError> {
+        let (cert, key) = (cert.into(), key.into());
+        let files = vec![cert.clone(), key.clone()];
+
+        Self::new(files, move || config_from_pem_files(&cert, &key))
+    }
+
+    /// returns the config new handshakes are made with
+    pub fn config(&self) -> Arc<ServerConfig> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// rebuilds the config and swaps it in
+    ///
+    /// ### Error
+    ///
+    /// returns the error the config failed to build with, the old config is kept
+    pub fn reload(&self) -> Result<(), TlsError> {
+        let Some(ref load) = self.load else {
+            return Ok(());
+        };
+        let config = Arc::new(load()?);
+        *self.current.write().unwrap() = config;
+
+        Ok(())
+    }
+
+    /// reloads the config every time the process receives a SIGHUP,
+    /// `reloaded` is called with the result of every reload
+    ///
+    /// does nothing on platforms without unix signals
+    pub fn reload_on_signal<F>(&self, reloaded: F) -> JoinHandle<()>
+    where
+        F: Fn(Result<(), TlsError>) + Send + 'static,
+    {
+        let tls = self.clone();
+
+        tokio::spawn(async move {
+            #[cfg(unix)]
+            {
+                use tokio::signal::unix::{SignalKind, signal};
+
+                let Ok(mut hangup) = signal(SignalKind::hangup()) else {
+                    return;
+                };
+                while hangup.recv().await.is_some() {
+                    reloaded(tls.reload());
+                }
+            }
+            #[cfg(not(unix))]
+            let _ = (tls, reloaded);
+        })
+    }
+
+    /// checks the config files for changes every `every` and reloads the config when they change,
+    /// `reloaded` is called with the result of every reload
+    pub fn watch<F>(&self, every: Duration, reloaded: F) -> JoinHandle<()>
+    where
+        F: Fn(Result<(), TlsError>) + Send + 'static,
+    {
+        let tls = self.clone();
+
+        tokio::spawn(async move {
+            let mut seen = tls.modified();
+            let mut interval = tokio::time::interval(every);
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+                let modified = tls.modified();
+                if modified != seen {
+                    seen = modified;
+                    reloaded(tls.reload());
+                }
+            }
+        })
+    }
+
+    // the last modification times of the config files
+    fn modified(&self) -> Vec<Option<SystemTime>> {
+        self.files
+            .iter()
+            .map(|file| fs::metadata(file).and_then(|meta| meta.modified()).ok())
+            .collect()
+    }
+}
+
+impl From<ServerConfig> for ReloadableTls {
+    fn from(config: ServerConfig) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(config))),
+            load: None,
+            files: Arc::new(vec![]),
+        }
+    }
+}
+
+impl fmt::Debug for ReloadableTls {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReloadableTls")
+            .field("files", &self.files)
+            .finish()
+    }
+}
+
+/// what was negotiated during the tls handshake of the connection a request came from
+///
+/// used as a service input type, `None` for plaintext connections
+///
+/// ```ignore
+/// #[get("/whoami")]
+/// async fn whoami(tls: Option<TlsInfo>) -> String {
+///     format!("{:?}", tls.map(|tls| tls.version()))
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsInfo {
+    version: Option<ProtocolVersion>,
+    cipher: Option<CipherSuite>,
+    alpn: Option<String>,
+    server_name: Option<String>,
+    client_cert: Option<ClientCert>,
+}
+
+impl TlsInfo {
+    pub(crate) fn new(conn: &ServerConnection) -> Self {
+        Self {
+            version: conn.protocol_version(),
+            cipher: conn.negotiated_cipher_suite().map(|cs| cs.suite()),
+            alpn: conn
+                .alpn_protocol()
+                .map(|alpn| String::from_utf8_lossy(alpn).into_owned()),
+            server_name: conn.server_name().map(|name| name.to_owned()),
+            client_cert: conn
+                .peer_certificates()
+                .filter(|chain| !chain.is_empty())
+                .map(|chain| ClientCert::new(chain.to_vec())),
+        }
+    }
+
+    /// returns the tls version of the connection
+    pub fn version(&self) -> Option<ProtocolVersion> {
+        self.version
+    }
+
+    /// returns the cipher suite of the connection
+    pub fn cipher(&self) -> Option<CipherSuite> {
+        self.cipher
+    }
+
+    /// returns the application protocol negotiated through alpn, e.g., "h2"
+    pub fn alpn(&self) -> Option<&str> {
+        self.alpn.as_deref()
+    }
+
+    /// returns the server name the client asked for through sni
+    pub fn server_name(&self) -> Option<&str> {
+        self.server_name.as_deref()
+    }
+
+    /// returns the verified certificate the client authenticated with, if any
+    pub fn client_cert(&self) -> Option<&ClientCert> {
+        self.client_cert.as_ref()
+    }
+}
+
+impl From<&Request> for Option<TlsInfo> {
+    fn from(req: &Request) -> Self {
+        req.tls().cloned()
+    }
+}
+
+/// the verified certificate chain a client authenticated with over mutual tls
+///
+/// used as a service input type, `None` if the client sent no certificate
+///
+/// ```ignore
+/// #[get("/internal")]
+/// async fn internal(cert: Option<ClientCert>) -> String {
+///     cert.map(|cert| cert.subject().to_owned()).unwrap_or_default()
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCert {
+    chain: Vec<CertificateDer<'static>>,
+    subject: String,
+}
+
+impl ClientCert {
+    fn new(chain: Vec<CertificateDer<'static>>) -> Self {
+        let subject = X509Certificate::from_der(&chain[0])
+            .map(|(_, cert)| cert.subject().to_string())
+            .unwrap_or_default();
+
+        Self { chain, subject }
+    }
+
+    /// returns the certificate chain, starting with the client's own certificate
+    pub fn chain(&self) -> &[CertificateDer<'static>] {
+        &self.chain
+    }
+
+    /// returns the subject distinguished name of the client's certificate, e.g., "CN=billing, O=acme"
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+}
+
+impl From<&Request> for Option<ClientCert> {
+    fn from(req: &Request) -> Self {
+        req.tls().and_then(|tls| tls.client_cert()).cloned()
+    }
 }
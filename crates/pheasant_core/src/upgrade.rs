@@ -0,0 +1,40 @@
+use std::fmt;
+use std::pin::Pin;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// a bidirectional connection stream,
+/// what an upgraded connection is handed over as
+pub trait Io: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T> Io for T where T: AsyncRead + AsyncWrite + Unpin + Send {}
+
+// the future return type
+type BoxFut = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+// the wrapper function type
+type BoxFun = Box<dyn FnOnce(Box<dyn Io>) -> BoxFut + Send>;
+
+/// takes over a connection once its 101 Switching Protocols response is sent
+pub struct Upgrade(BoxFun);
+
+impl Upgrade {
+    pub fn new<F, O>(handler: F) -> Self
+    where
+        F: FnOnce(Box<dyn Io>) -> O + Send + 'static,
+        O: Future<Output = ()> + Send + 'static,
+    {
+        Self(Box::new(move |io| Box::pin(handler(io))))
+    }
+
+    // runs the upgraded protocol until it is done with the connection
+    pub(crate) async fn run(self, io: Box<dyn Io>) {
+        (self.0)(io).await
+    }
+}
+
+impl fmt::Debug for Upgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Upgrade")
+    }
+}
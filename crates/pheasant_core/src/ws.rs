@@ -0,0 +1,527 @@
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use sha1::{Digest, Sha1};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, ReadHalf, WriteHalf};
+use tokio::sync::Mutex;
+
+use crate::{HeaderMap, Method, Request, Response, Upgrade, upgrade::Io};
+
+// appended to the client key before hashing it into the accept key
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+// close status codes
+const NORMAL: u16 = 1000;
+const PROTOCOL_ERROR: u16 = 1002;
+const INVALID_DATA: u16 = 1007;
+const TOO_BIG: u16 = 1009;
+
+// frame opcodes
+const CONTINUATION: u8 = 0x0;
+const TEXT: u8 = 0x1;
+const BINARY: u8 = 0x2;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xA;
+
+/// a websocket message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    /// the close status code and reason, if the peer sent any
+    Close(Option<(u16, String)>),
+}
+
+impl Message {
+    // the frame opcode and payload of this message
+    fn into_frame(self) -> (u8, Vec<u8>) {
+        match self {
+            Self::Text(text) => (TEXT, text.into_bytes()),
+            Self::Binary(data) => (BINARY, data),
+            Self::Ping(data) => (PING, data),
+            Self::Pong(data) => (PONG, data),
+            Self::Close(None) => (CLOSE, vec![]),
+            Self::Close(Some((code, reason))) => {
+                let mut payload = code.to_be_bytes().to_vec();
+                payload.extend(reason.into_bytes());
+
+                (CLOSE, payload)
+            }
+        }
+    }
+}
+
+// size limits of the incoming messages
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    message: usize,
+    frame: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            message: 64 << 20,
+            frame: 16 << 20,
+        }
+    }
+}
+
+/// a websocket upgrade request,
+/// used as a service input type
+///
+/// ```ignore
+/// #[get("/echo")]
+/// async fn echo(ws: WsUpgrade) -> Response {
+///     ws.on_upgrade(|mut ws| async move {
+///         while let Some(msg) = ws.recv().await {
+///             if let Message::Text(text) = msg {
+///                 _ = ws.send(Message::Text(text)).await;
+///             }
+///         }
+///     })
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WsUpgrade {
+    key: Option<String>,
+    version: Option<String>,
+    // wether the request asked for a websocket upgrade at all
+    upgrade: bool,
+    protocols: Vec<String>,
+    protocol: Option<String>,
+    limits: Limits,
+}
+
+impl From<&Request> for WsUpgrade {
+    fn from(req: &Request) -> Self {
+        let upgrade = req.method() == Method::Get
+            && req.connection_has("upgrade")
+            && req.header::<String>("Upgrade").is_some_and(|up| {
+                up.split(',')
+                    .any(|proto| proto.trim().eq_ignore_ascii_case("websocket"))
+            });
+        let protocols = req
+            .header::<String>("Sec-WebSocket-Protocol")
+            .map(|protos| {
+                protos
+                    .split(',')
+                    .map(|proto| proto.trim().to_owned())
+                    .filter(|proto| !proto.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            key: req.header::<String>("Sec-WebSocket-Key"),
+            version: req.header::<String>("Sec-WebSocket-Version"),
+            upgrade,
+            protocols,
+            protocol: None,
+            limits: Limits::default(),
+        }
+    }
+}
+
+impl WsUpgrade {
+    /// returns the subprotocols the client offered, in its order of preference
+    pub fn protocols(&self) -> &[String] {
+        &self.protocols
+    }
+
+    /// picks the subprotocol the connection is going to speak
+    ///
+    /// ignored if the client didn't offer it
+    pub fn protocol(mut self, protocol: &str) -> Self {
+        self.protocol = self
+            .protocols
+            .iter()
+            .find(|proto| proto.as_str() == protocol)
+            .cloned();
+
+        self
+    }
+
+    /// sets the max size of a whole (reassembled) incoming message, defaults to 64 MiB
+    pub fn max_message(mut self, size: usize) -> Self {
+        self.limits.message = size;
+
+        self
+    }
+
+    /// sets the max size of a single incoming frame, defaults to 16 MiB
+    pub fn max_frame(mut self, size: usize) -> Self {
+        self.limits.frame = size;
+
+        self
+    }
+
+    /// accepts the upgrade and hands the connection to `handler` once the handshake is done
+    ///
+    /// if the request isn't a valid websocket upgrade,
+    /// returns a 400 Bad Request, or a 426 Upgrade Required for unsupported versions
+    pub fn on_upgrade<F, O>(self, handler: F) -> Response
+    where
+        F: FnOnce(WebSocket) -> O + Send + 'static,
+        O: Future<Output = ()> + Send + 'static,
+    {
+        let Some(accept) = self.accept() else {
+            return self.reject();
+        };
+
+        let mut resp = Response::with_status(101);
+        resp.set_header::<String>("Upgrade", "websocket".into())
+            .set_header::<String>("Connection", "Upgrade".into())
+            .set_header("Sec-WebSocket-Accept", accept);
+        if let Some(protocol) = self.protocol {
+            resp.set_header("Sec-WebSocket-Protocol", protocol);
+        }
+
+        let limits = self.limits;
+        resp.set_upgrade(Upgrade::new(move |io| handler(WebSocket::new(io, limits))));
+
+        resp
+    }
+
+    // computes the Sec-WebSocket-Accept value
+    // returns None if the request is not a valid version 13 upgrade
+    fn accept(&self) -> Option<String> {
+        if !self.upgrade || self.version.as_deref() != Some("13") {
+            return None;
+        }
+        let key = self.key.as_deref()?.trim();
+        if BASE64.decode(key).ok()?.len() != 16 {
+            return None;
+        }
+
+        let mut sha = Sha1::new();
+        sha.update(key.as_bytes());
+        sha.update(GUID.as_bytes());
+
+        Some(BASE64.encode(sha.finalize()))
+    }
+
+    fn reject(&self) -> Response {
+        if self.upgrade && self.version.as_deref() != Some("13") {
+            let mut resp = Response::with_status(426);
+            resp.set_header::<String>("Sec-WebSocket-Version", "13".into());
+
+            resp
+        } else {
+            Response::with_status(400)
+        }
+    }
+}
+
+/// an upgraded websocket connection
+///
+/// can be split into a receiving and a (cloneable) sending half
+/// to read and write from different tasks
+pub struct WebSocket {
+    rx: WsReceiver,
+    tx: WsSender,
+}
+
+impl WebSocket {
+    fn new(io: Box<dyn Io>, limits: Limits) -> Self {
+        let (read, write) = tokio::io::split(io);
+        let tx = WsSender {
+            write: Arc::new(Mutex::new(write)),
+            closed: Arc::new(AtomicBool::new(false)),
+        };
+
+        Self {
+            rx: WsReceiver {
+                read,
+                tx: tx.clone(),
+                limits,
+                fragments: None,
+                done: false,
+            },
+            tx,
+        }
+    }
+
+    /// waits for the next message
+    ///
+    /// returns None once the connection is closed
+    pub async fn recv(&mut self) -> Option<Message> {
+        self.rx.recv().await
+    }
+
+    /// sends a message to the client
+    pub async fn send(&mut self, msg: Message) -> io::Result<()> {
+        self.tx.send(msg).await
+    }
+
+    /// starts the closing handshake
+    pub async fn close(&mut self, code: u16, reason: &str) -> io::Result<()> {
+        self.tx.close(code, reason).await
+    }
+
+    /// splits the connection into its sending and receiving halves
+    pub fn split(self) -> (WsSender, WsReceiver) {
+        (self.tx, self.rx)
+    }
+}
+
+/// the sending half of a websocket connection
+#[derive(Clone)]
+pub struct WsSender {
+    write: Arc<Mutex<WriteHalf<Box<dyn Io>>>>,
+    // set once a close frame was sent, nothing can be sent after it
+    closed: Arc<AtomicBool>,
+}
+
+impl WsSender {
+    /// sends a message to the client
+    pub async fn send(&self, msg: Message) -> io::Result<()> {
+        let (opcode, payload) = msg.into_frame();
+        if opcode & 0x8 != 0 && payload.len() > 125 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "control frames payloads can't exceed 125 bytes",
+            ));
+        }
+
+        let mut write = self.write.lock().await;
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "the websocket is closed",
+            ));
+        }
+        if opcode == CLOSE {
+            self.closed.store(true, Ordering::SeqCst);
+        }
+
+        write.write_all(&frame(opcode, &payload)).await?;
+        write.flush().await
+    }
+
+    /// starts the closing handshake
+    pub async fn close(&self, code: u16, reason: &str) -> io::Result<()> {
+        self.send(Message::Close(Some((code, reason.to_owned()))))
+            .await
+    }
+
+    /// checks if a close frame was already sent
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+}
+
+// formats an unmasked, final frame
+fn frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend((len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend((len as u64).to_be_bytes());
+        }
+    }
+    frame.extend(payload);
+
+    frame
+}
+
+/// the receiving half of a websocket connection
+///
+/// pings are answered and close frames acknowledged as they are received
+pub struct WsReceiver {
+    read: ReadHalf<Box<dyn Io>>,
+    tx: WsSender,
+    limits: Limits,
+    // the opcode and payload of a fragmented message being reassembled
+    fragments: Option<(u8, Vec<u8>)>,
+    done: bool,
+}
+
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+// why the connection can't go on
+enum Broken {
+    // the underlying stream failed or was closed without a close frame
+    Lost,
+    // the peer violated the protocol, the connection is closed with this code
+    Close(u16),
+}
+
+impl WsReceiver {
+    /// waits for the next message
+    ///
+    /// returns None once the connection is closed
+    pub async fn recv(&mut self) -> Option<Message> {
+        if self.done {
+            return None;
+        }
+
+        match self.next_message().await {
+            Ok(msg) => {
+                if let Message::Close(_) = msg {
+                    self.done = true;
+                }
+
+                Some(msg)
+            }
+            Err(broken) => {
+                if let Broken::Close(code) = broken {
+                    _ = self.tx.close(code, "").await;
+                }
+                self.done = true;
+
+                None
+            }
+        }
+    }
+
+    async fn next_message(&mut self) -> Result<Message, Broken> {
+        loop {
+            let Frame {
+                fin,
+                opcode,
+                payload,
+            } = self.read_frame().await?;
+
+            let (opcode, payload) = match opcode {
+                TEXT | BINARY if self.fragments.is_some() => {
+                    return Err(Broken::Close(PROTOCOL_ERROR));
+                }
+                TEXT | BINARY if !fin => {
+                    self.fragments = Some((opcode, payload));
+                    continue;
+                }
+                TEXT | BINARY => (opcode, payload),
+                CONTINUATION => {
+                    let Some((_, ref mut message)) = self.fragments else {
+                        return Err(Broken::Close(PROTOCOL_ERROR));
+                    };
+                    if message.len() + payload.len() > self.limits.message {
+                        return Err(Broken::Close(TOO_BIG));
+                    }
+                    message.extend(payload);
+                    if !fin {
+                        continue;
+                    }
+
+                    self.fragments.take().unwrap()
+                }
+                PING => {
+                    _ = self.tx.send(Message::Pong(payload.clone())).await;
+
+                    return Ok(Message::Ping(payload));
+                }
+                PONG => return Ok(Message::Pong(payload)),
+                CLOSE => return self.closing(payload).await,
+                _ => return Err(Broken::Close(PROTOCOL_ERROR)),
+            };
+
+            return match opcode {
+                TEXT => String::from_utf8(payload)
+                    .map(Message::Text)
+                    .map_err(|_| Broken::Close(INVALID_DATA)),
+                _ => Ok(Message::Binary(payload)),
+            };
+        }
+    }
+
+    // acknowledges the peer's close frame
+    async fn closing(&mut self, payload: Vec<u8>) -> Result<Message, Broken> {
+        let close = match payload.len() {
+            0 => None,
+            1 => return Err(Broken::Close(PROTOCOL_ERROR)),
+            _ => {
+                let code = u16::from_be_bytes([payload[0], payload[1]]);
+                let reason = String::from_utf8(payload[2..].to_vec())
+                    .map_err(|_| Broken::Close(INVALID_DATA))?;
+
+                Some((code, reason))
+            }
+        };
+
+        if !self.tx.is_closed() {
+            let code = close.as_ref().map_or(NORMAL, |(code, _)| *code);
+            _ = self.tx.close(code, "").await;
+        }
+
+        Ok(Message::Close(close))
+    }
+
+    async fn read_frame(&mut self) -> Result<Frame, Broken> {
+        let mut head = [0; 2];
+        self.read_exact(&mut head).await?;
+
+        let fin = head[0] & 0x80 != 0;
+        // no extensions are negotiated, so the reserved bits have to be 0
+        if head[0] & 0x70 != 0 {
+            return Err(Broken::Close(PROTOCOL_ERROR));
+        }
+        let opcode = head[0] & 0x0F;
+        // client frames have to be masked
+        if head[1] & 0x80 == 0 {
+            return Err(Broken::Close(PROTOCOL_ERROR));
+        }
+
+        let len = match head[1] & 0x7F {
+            126 => {
+                let mut len = [0; 2];
+                self.read_exact(&mut len).await?;
+
+                u16::from_be_bytes(len) as u64
+            }
+            127 => {
+                let mut len = [0; 8];
+                self.read_exact(&mut len).await?;
+
+                u64::from_be_bytes(len)
+            }
+            len => len as u64,
+        };
+        if opcode & 0x8 != 0 && (!fin || len > 125) {
+            return Err(Broken::Close(PROTOCOL_ERROR));
+        }
+        // a single frame can't carry more than a whole message either
+        if len > self.limits.frame.min(self.limits.message) as u64 {
+            return Err(Broken::Close(TOO_BIG));
+        }
+
+        let mut mask = [0; 4];
+        self.read_exact(&mut mask).await?;
+        let mut payload = vec![0; len as usize];
+        self.read_exact(&mut payload).await?;
+        payload
+            .iter_mut()
+            .enumerate()
+            .for_each(|(idx, b)| *b ^= mask[idx % 4]);
+
+        Ok(Frame {
+            fin,
+            opcode,
+            payload,
+        })
+    }
+
+    async fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Broken> {
+        self.read
+            .read_exact(buf)
+            .await
+            .map(|_| ())
+            .map_err(|_| Broken::Lost)
+    }
+}
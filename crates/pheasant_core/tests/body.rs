@@ -0,0 +1,107 @@
+mod common;
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use common::{request, server};
+use futures_core::Stream;
+use pheasant_core::{BodyStream, Method, Protocol, Response, Server, Service};
+use pheasant_uri::Route;
+
+// yields its chunks one by one
+struct Chunks(VecDeque<&'static [u8]>);
+
+impl Stream for Chunks {
+    type Item = Vec<u8>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.0.pop_front().map(<[u8]>::to_vec))
+    }
+}
+
+fn chunks() -> Chunks {
+    // the empty chunk would end the chunked body early if it were sent
+    Chunks(VecDeque::from([&b"hello"[..], b"", b", ", b"world"]))
+}
+
+async fn chunked(_: (), proto: Protocol) -> Response {
+    let mut resp = Response::with_proto(proto);
+    resp.update_body(BodyStream::from_stream(chunks()));
+
+    resp
+}
+
+async fn sized(_: (), proto: Protocol) -> Response {
+    let mut resp = Response::with_proto(proto);
+    resp.update_body(BodyStream::from_reader(&b"hello, world"[..]).with_len(12));
+
+    resp
+}
+
+// the shared test server with the services of these tests
+fn app() -> Server {
+    let mut server = server();
+    server
+        .service(|| {
+            Service::new(
+                Method::Get,
+                Route::macro_checked("/chunked"),
+                None,
+                None,
+                None,
+                chunked,
+            )
+        })
+        .service(|| {
+            Service::new(
+                Method::Get,
+                Route::macro_checked("/sized"),
+                None,
+                None,
+                None,
+                sized,
+            )
+        });
+
+    server
+}
+
+#[test]
+fn content_length() {
+    assert_eq!(BodyStream::from_stream(chunks()).content_length(), None);
+    assert!(BodyStream::from_stream(chunks()).is_chunked());
+
+    let stream = BodyStream::from_reader(&b"hello"[..]).with_len(5);
+    assert_eq!(stream.content_length(), Some(5));
+    assert!(!stream.is_chunked());
+}
+
+#[tokio::test]
+async fn chunked_framing() {
+    let resps = request(app(), &["GET /chunked HTTP/1.1"]).await;
+    let resp = &resps[0];
+
+    assert!(resp.starts_with("HTTP/1.1 200"), "{}", resp);
+    assert!(resp.contains("Transfer-Encoding: chunked\n"), "{}", resp);
+    assert!(!resp.contains("Content-Length"), "{}", resp);
+    assert!(
+        resp.ends_with("\n\n5\r\nhello\r\n2\r\n, \r\n5\r\nworld\r\n0\r\n\r\n"),
+        "{:?}",
+        resp
+    );
+}
+
+#[tokio::test]
+async fn sized_stream() {
+    let resps = request(app(), &["GET /sized HTTP/1.1", "HEAD /sized HTTP/1.1"]).await;
+    let (get, head) = (&resps[0], &resps[1]);
+
+    assert!(get.contains("Content-Length: 12\n"), "{}", get);
+    assert!(!get.contains("Transfer-Encoding"), "{}", get);
+    // sent as is, without the chunk framing
+    assert!(get.ends_with("\n\nhello, world"), "{:?}", get);
+
+    assert!(head.contains("Content-Length: 12\n"), "{}", head);
+    assert!(head.ends_with("\n\n"), "{:?}", head);
+}
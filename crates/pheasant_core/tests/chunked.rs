@@ -0,0 +1,118 @@
+mod common;
+
+use common::{exchange, server};
+use pheasant_core::{HeaderMap, Method, Protocol, Request, Response, Server, Service};
+use pheasant_uri::Route;
+
+// echoes the request body and its checksum trailer in the headers
+async fn echo(req: Request, proto: Protocol) -> Response {
+    let mut resp = Response::with_proto(proto);
+    if let Some(checksum) = req.trailer("X-Checksum") {
+        resp.set_header::<String>("X-Checksum", checksum.into());
+    }
+    resp.set_header::<String>("X-Body", req.body().unwrap_or_default().into());
+
+    resp
+}
+
+// the shared test server with the services of these tests
+fn app() -> Server {
+    let mut server = server();
+    server.service(|| {
+        Service::new(
+            Method::Post,
+            Route::macro_checked("/echo"),
+            None,
+            None,
+            None,
+            echo,
+        )
+    });
+
+    server
+}
+
+// a chunked post request of the raw body
+fn post(body: &str) -> String {
+    format!(
+        "POST /echo HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n{}",
+        body
+    )
+}
+
+#[tokio::test]
+async fn extensions() {
+    let resps = exchange(
+        app(),
+        &[post(
+            "5;name=value\r\nhello\r\n7 ; last\r\n, world\r\n0;end\r\n\r\n",
+        )],
+    )
+    .await;
+
+    assert!(resps[0].starts_with("HTTP/1.1 200"), "{}", resps[0]);
+    assert!(resps[0].contains("X-Body: hello, world\n"), "{}", resps[0]);
+}
+
+#[tokio::test]
+async fn trailers() {
+    let resps = exchange(
+        app(),
+        &[post("5\r\nhello\r\n0\r\nX-Checksum: 5d41\r\n\r\n")],
+    )
+    .await;
+
+    assert!(resps[0].starts_with("HTTP/1.1 200"), "{}", resps[0]);
+    assert!(resps[0].contains("X-Checksum: 5d41\n"), "{}", resps[0]);
+    assert!(resps[0].contains("X-Body: hello\n"), "{}", resps[0]);
+}
+
+#[tokio::test]
+async fn bad_sizes() {
+    let resps = exchange(
+        app(),
+        &[
+            post("zz\r\nhello\r\n0\r\n\r\n"),
+            post("-5\r\nhello\r\n0\r\n\r\n"),
+            post("10000000000000000000\r\nhello\r\n0\r\n\r\n"),
+            post("\r\nhello\r\n0\r\n\r\n"),
+        ],
+    )
+    .await;
+
+    // none of them fall through to the 501 of a missing failure
+    for resp in resps {
+        assert!(resp.starts_with("HTTP/1.1 400"), "{}", resp);
+    }
+}
+
+#[tokio::test]
+async fn missing_crlf() {
+    let resps = exchange(
+        app(),
+        &[post("5\r\nhello0\r\n\r\n"), post("5\r\nhelloXX0\r\n\r\n")],
+    )
+    .await;
+
+    for resp in resps {
+        assert!(resp.starts_with("HTTP/1.1 400"), "{}", resp);
+    }
+}
+
+#[tokio::test]
+async fn too_large() {
+    let resps = exchange(
+        app(),
+        &[
+            post("900000\r\n"),
+            // each chunk fits, the decoded body doesn't
+            post("5\r\nhello\r\n7ffffe\r\n"),
+            "POST /echo HTTP/1.1\r\nHost: localhost\r\nContent-Length: 9437184\r\n\r\n".into(),
+        ],
+    )
+    .await;
+
+    for resp in resps {
+        assert!(resp.starts_with("HTTP/1.1 413"), "{}", resp);
+    }
+}
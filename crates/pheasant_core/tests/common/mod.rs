@@ -0,0 +1,90 @@
+// helpers shared by the integration tests, every test crate only uses some of them
+#![allow(dead_code)]
+
+use std::collections::HashSet;
+use std::net::SocketAddr;
+
+use pheasant_core::{Method, Protocol, Response, Server, Service};
+use pheasant_uri::Route;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+pub const HELLO: &[u8] = b"hello, world";
+
+pub async fn hello(_: (), proto: Protocol) -> Response {
+    let mut resp = Response::with_proto(proto);
+    resp.update_body(HELLO.to_vec());
+
+    resp
+}
+
+/// a service of the route that answers with `HELLO`
+pub fn service(method: Method, route: &str) -> Service {
+    Service::new(method, Route::macro_checked(route), None, None, None, hello)
+}
+
+/// a service of the route that the redirect routes lead to, it answers with `HELLO`
+pub fn redirecting(method: Method, route: &str, redirects: &[&str]) -> Service {
+    let redirects = redirects
+        .iter()
+        .map(|r| Route::macro_checked(r))
+        .collect::<HashSet<_>>();
+    let redirects = (!redirects.is_empty()).then_some(redirects);
+
+    Service::new(
+        method,
+        Route::macro_checked(route),
+        redirects,
+        None,
+        None,
+        hello,
+    )
+}
+
+/// a server on a port picked by the os, so tests running at the same time can't collide
+///
+/// the port is read back with `Server::local_addr`
+pub fn server() -> Server {
+    Server::new([127, 0, 0, 1], 0, 8).unwrap()
+}
+
+/// serves the requests, each on its own connection, and returns the raw responses
+///
+/// a request is its request line, optionally followed by header lines,
+/// e.g., "GET / HTTP/1.1\r\nOrigin: http://app.example",
+/// the host header and the connection close are appended to it
+pub async fn request(server: Server, reqs: &[&str]) -> Vec<String> {
+    let reqs = reqs
+        .iter()
+        .map(|req| format!("{}\r\nHost: localhost\r\nConnection: close\r\n\r\n", req))
+        .collect::<Vec<_>>();
+
+    exchange(server, &reqs).await
+}
+
+/// serves the raw requests, each on its own connection, and returns the raw responses
+pub async fn exchange(mut server: Server, reqs: &[impl AsRef<[u8]>]) -> Vec<String> {
+    let addr = server.local_addr().unwrap();
+    let shutdown = server.shutdown_handle();
+    let serving = tokio::spawn(async move { server.serve().await });
+
+    let mut resps = vec![];
+    for req in reqs {
+        resps.push(send(addr, req.as_ref()).await);
+    }
+
+    shutdown.shutdown();
+    serving.await.unwrap();
+
+    resps
+}
+
+/// writes the raw request to a new connection and reads the response until the server closes it
+pub async fn send(addr: SocketAddr, req: &[u8]) -> String {
+    let mut tcp = TcpStream::connect(addr).await.unwrap();
+    tcp.write_all(req).await.unwrap();
+    let mut resp = vec![];
+    tcp.read_to_end(&mut resp).await.unwrap();
+
+    String::from_utf8_lossy(&resp).into_owned()
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::server;
+use pheasant_core::tls::{DevCa, KeyType};
+use pheasant_core::{Method, Protocol, Response, Service};
+use pheasant_uri::Route;
+use rustls::client::danger::ServerCertVerifier;
+use rustls::crypto::ring::default_provider;
+use rustls::{ClientConfig, RootCertStore};
+use rustls_pki_types::ServerName;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+
+async fn hello(_: (), proto: Protocol) -> Response {
+    let mut resp = Response::with_proto(proto);
+    resp.update_body(b"hello".to_vec());
+
+    resp
+}
+
+fn client(ca: &DevCa) -> TlsConnector {
+    let mut roots = RootCertStore::empty();
+    roots.add(ca.cert().clone()).unwrap();
+    let config = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    TlsConnector::from(Arc::new(config))
+}
+
+#[tokio::test]
+async fn verified_connection() {
+    for key in [KeyType::EcdsaP256, KeyType::EcdsaP384, KeyType::Ed25519] {
+        let ca = DevCa::generate("test ca", key, 1).unwrap();
+        let cert = ca.issue(&["localhost".into()], key, 1).unwrap();
+
+        let mut server = server();
+        let addr = server.local_addr().unwrap();
+        let shutdown = server.shutdown_handle();
+        server.tls(cert.config().unwrap()).service(|| {
+            Service::new(
+                Method::Get,
+                Route::macro_checked("/"),
+                None,
+                None,
+                None,
+                hello,
+            )
+        });
+        let serving = tokio::spawn(async move { server.serve().await });
+
+        let tcp = TcpStream::connect(addr).await.unwrap();
+        let name = ServerName::try_from("localhost").unwrap();
+        let mut tls = client(&ca).connect(name, tcp).await.unwrap();
+        tls.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut resp = vec![];
+        tls.read_to_end(&mut resp).await.unwrap();
+        let resp = String::from_utf8_lossy(&resp);
+
+        assert!(resp.starts_with("HTTP/1.1 200"), "{}", resp);
+
+        shutdown.shutdown();
+        serving.await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn untrusted_ca() {
+    let ca = DevCa::generate("test ca", KeyType::default(), 1).unwrap();
+    let other = DevCa::generate("other ca", KeyType::default(), 1).unwrap();
+    let cert = other
+        .issue(&["localhost".into()], KeyType::default(), 1)
+        .unwrap();
+
+    let mut server = server();
+    let addr = server.local_addr().unwrap();
+    let shutdown = server.shutdown_handle();
+    server.tls(cert.config().unwrap());
+    let serving = tokio::spawn(async move { server.serve().await });
+
+    let tcp = TcpStream::connect(addr).await.unwrap();
+    let name = ServerName::try_from("localhost").unwrap();
+
+    assert!(client(&ca).connect(name, tcp).await.is_err());
+
+    shutdown.shutdown();
+    serving.await.unwrap();
+}
+
+#[test]
+fn write_and_load() {
+    let dir = std::env::temp_dir().join(format!("pheasant-dev-ca-{}", std::process::id()));
+    let ca = DevCa::generate("test ca", KeyType::default(), 1).unwrap();
+    ca.write(&dir).unwrap();
+
+    let loaded = DevCa::load(&dir).unwrap();
+    assert_eq!(loaded.cert(), ca.cert());
+
+    // certificates issued by the loaded ca chain up to the written one
+    let cert = loaded
+        .issue(&["localhost".into()], KeyType::default(), 1)
+        .unwrap();
+    cert.write(&dir, "localhost").unwrap();
+    let written = pheasant_core::tls::load_certs(dir.join("localhost.pem")).unwrap();
+    assert_eq!(&written[0], cert.cert());
+
+    let mut roots = RootCertStore::empty();
+    roots.add(ca.cert().clone()).unwrap();
+    let verifier = rustls::client::WebPkiServerVerifier::builder_with_provider(
+        Arc::new(roots),
+        Arc::new(default_provider()),
+    )
+    .build()
+    .unwrap();
+    let name = ServerName::try_from("localhost").unwrap();
+    verifier
+        .verify_server_cert(
+            cert.cert(),
+            &[],
+            &name,
+            &[],
+            rustls_pki_types::UnixTime::now(),
+        )
+        .unwrap();
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::{request, server, service};
+use pheasant_core::{Method, Protocol, Response, Service};
+use pheasant_uri::Route;
+
+async fn head(_: (), proto: Protocol) -> Response {
+    let mut resp = Response::with_proto(proto);
+    resp.update_body(b"by the head service".to_vec());
+
+    resp
+}
+
+fn content_length(resp: &str) -> &str {
+    resp.lines()
+        .find_map(|line| line.strip_prefix("Content-Length: "))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn runs_get_service() {
+    let mut server = server();
+    server.service(|| service(Method::Get, "/hello"));
+    let resps = request(server, &["GET /hello HTTP/1.1", "HEAD /hello HTTP/1.1"]).await;
+    let (get, head) = (&resps[0], &resps[1]);
+
+    assert!(head.starts_with("HTTP/1.1 200"), "{}", head);
+    // the same length as the get response, without the body
+    assert_eq!(content_length(head), content_length(get));
+    assert!(head.ends_with("\n\n"), "{}", head);
+    assert!(!get.ends_with("\n\n"), "{}", get);
+}
+
+#[tokio::test]
+async fn head_service_first() {
+    let mut server = server();
+    server
+        .service(|| service(Method::Get, "/hello"))
+        .service(|| {
+            Service::new(
+                Method::Head,
+                Route::macro_checked("/hello"),
+                None,
+                None,
+                None,
+                head,
+            )
+        });
+    let resps = request(
+        server,
+        &[
+            "GET /hello HTTP/1.1",
+            "HEAD /hello HTTP/1.1",
+            "HEAD /missing HTTP/1.1",
+        ],
+    )
+    .await;
+    let (get, head, missing) = (&resps[0], &resps[1], &resps[2]);
+
+    assert_ne!(content_length(head), content_length(get));
+    assert!(head.ends_with("\n\n"), "{}", head);
+    assert!(!missing.starts_with("HTTP/1.1 200"), "{}", missing);
+}
+
+#[test]
+fn service_status() {
+    let mut server = server();
+    server.service(|| service(Method::Get, "/users/:id"));
+
+    let (_, service) = server.service_status(Method::Head, "/users/42").unwrap();
+    assert_eq!(service.route(), "/users/:id");
+    assert!(server.service_status(Method::Head, "/posts/42").is_err());
+}
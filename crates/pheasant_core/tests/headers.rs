@@ -0,0 +1,74 @@
+mod common;
+
+use common::{exchange, server, service};
+use pheasant_core::{HeaderMap, Method, Protocol, Request, Response, Server, Service};
+use pheasant_uri::Route;
+
+// echoes the request header and body the service sees
+async fn echo(req: Request, proto: Protocol) -> Response {
+    let mut resp = Response::with_proto(proto);
+    resp.set_header(
+        "X-Echo",
+        req.header::<String>("X-Token").unwrap_or_default(),
+    )
+    .set_header::<String>("X-Body", req.body().unwrap_or_default().into());
+
+    resp
+}
+
+// the shared test server with the services of these tests
+fn app() -> Server {
+    let mut server = server();
+    server
+        .service(|| service(Method::Get, "/hello"))
+        .service(|| {
+            Service::new(
+                Method::Post,
+                Route::macro_checked("/echo"),
+                None,
+                None,
+                None,
+                echo,
+            )
+        });
+
+    server
+}
+
+#[tokio::test]
+async fn lowercase_names() {
+    let resps = exchange(
+        app(),
+        &[
+            "GET /hello HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n",
+            "GET /hello HTTP/1.1\r\nHOST: localhost\r\nConnection: close\r\n\r\n",
+            "POST /echo HTTP/1.1\r\nhost: localhost\r\nx-token: 42\r\n\
+            transfer-encoding: chunked\r\nconnection: close\r\n\r\n5\r\nhello\r\n0\r\n\r\n",
+        ],
+    )
+    .await;
+
+    for resp in &resps {
+        assert!(resp.starts_with("HTTP/1.1 200"), "{}", resp);
+        // the lowercase connection header was honoured too
+        assert!(resp.contains("Connection: close\n"), "{}", resp);
+    }
+    assert!(resps[2].contains("X-Echo: 42\n"), "{}", resps[2]);
+    assert!(resps[2].contains("X-Body: hello\n"), "{}", resps[2]);
+}
+
+#[tokio::test]
+async fn missing_host() {
+    let resps = exchange(
+        app(),
+        &[
+            "GET /hello HTTP/1.1\r\nConnection: close\r\n\r\n",
+            // http 1.0 doesn't require it
+            "GET /hello HTTP/1.0\r\n\r\n",
+        ],
+    )
+    .await;
+
+    assert!(resps[0].starts_with("HTTP/1.1 400"), "{}", resps[0]);
+    assert!(resps[1].contains(" 200 "), "{}", resps[1]);
+}
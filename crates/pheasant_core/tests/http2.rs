@@ -0,0 +1,182 @@
+mod common;
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use common::server;
+use h2::client::{self, SendRequest};
+use pheasant_core::middleware::BoxFuture;
+use pheasant_core::{HeaderMap, Method, Middleware, Protocol, Request, Response, Server, Service};
+use pheasant_uri::Route;
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+
+// echoes the headers the service sees
+async fn echo(req: Request, proto: Protocol) -> Response {
+    let mut resp = Response::with_proto(proto);
+    for name in ["Host", "Cookie", "X-Custom", "Content-Type"] {
+        if let Some(value) = req.header::<String>(name) {
+            resp.set_header(&format!("X-Echo-{}", name), value);
+        }
+    }
+
+    resp
+}
+
+// answers every request with a response it didn't give a status to
+struct Maintenance;
+
+impl Middleware for Maintenance {
+    fn before<'a>(&'a self, _: &'a mut Request) -> BoxFuture<'a, Option<Response>> {
+        Box::pin(async { Some(Response::default()) })
+    }
+}
+
+// the shared test server with the services of these tests
+fn app() -> Server {
+    let mut server = server();
+    for method in [Method::Get, Method::Post] {
+        server.service(move || {
+            Service::new(
+                method,
+                Route::macro_checked("/echo"),
+                None,
+                None,
+                None,
+                echo,
+            )
+        });
+    }
+
+    server
+}
+
+// opens a plaintext http/2 connection with prior knowledge
+async fn connect(mut server: Server) -> (SendRequest<Bytes>, JoinHandle<Result<(), h2::Error>>) {
+    let addr = server.local_addr().unwrap();
+    tokio::spawn(async move { server.serve().await });
+
+    let tcp = TcpStream::connect(addr).await.unwrap();
+    // the small frames of the body upload would otherwise wait on delayed acks
+    tcp.set_nodelay(true).unwrap();
+    let (client, conn) = client::handshake(tcp).await.unwrap();
+
+    (client, tokio::spawn(conn))
+}
+
+fn get() -> http::Request<()> {
+    http::Request::get("http://localhost/echo")
+        .body(())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn prior_knowledge() {
+    let (mut client, _) = connect(app()).await;
+
+    let req = http::Request::get("http://localhost:20943/echo")
+        .header("x-custom", "a")
+        .header("x-custom", "b")
+        .header("cookie", "a=1")
+        .header("cookie", "b=2")
+        .header("content-type", "text/plain")
+        .body(())
+        .unwrap();
+    let (resp, _) = client.send_request(req, true).unwrap();
+    let resp = resp.await.unwrap();
+
+    assert_eq!(resp.version(), http::Version::HTTP_2);
+    assert_eq!(resp.status(), 200);
+    let headers = resp.headers();
+    // :authority stands in for the host header
+    assert_eq!(headers["x-echo-host"], "localhost:20943");
+    // the repeated fields are joined, cookies with their own separator
+    assert_eq!(headers["x-echo-x-custom"], "a, b");
+    assert_eq!(headers["x-echo-cookie"], "a=1; b=2");
+    // the lowercase names are found under their capitalized form
+    assert_eq!(headers["x-echo-content-type"], "text/plain");
+    assert!(!headers.contains_key("connection"));
+}
+
+#[tokio::test]
+async fn body_too_large() {
+    let (mut client, _) = connect(app()).await;
+
+    let req = http::Request::post("http://localhost/echo")
+        .body(())
+        .unwrap();
+    let (resp, mut body) = client.send_request(req, false).unwrap();
+    let chunk = Bytes::from(vec![b'a'; 1 << 20]);
+    // 9 MiB, past the 8 MiB cap, the server may answer before it is all sent
+    for _ in 0..9 {
+        body.reserve_capacity(chunk.len());
+        let mut data = chunk.clone();
+        while !data.is_empty() {
+            let capacity = match std::future::poll_fn(|cx| body.poll_capacity(cx)).await {
+                Some(Ok(capacity)) => capacity,
+                _ => break,
+            };
+            if body
+                .send_data(data.split_to(capacity.min(data.len())), false)
+                .is_err()
+            {
+                break;
+            }
+        }
+    }
+    _ = body.send_data(Bytes::new(), true);
+
+    assert_eq!(resp.await.unwrap().status(), 413);
+}
+
+#[tokio::test]
+async fn idle_timeout() {
+    let mut server = app();
+    server.keep_alive(Duration::from_millis(200), 100);
+    let (mut client, conn) = connect(server).await;
+
+    let (resp, _) = client.send_request(get(), true).unwrap();
+    assert_eq!(resp.await.unwrap().status(), 200);
+
+    // the server sends a goaway once the connection idles and closes it
+    timeout(Duration::from_secs(5), conn)
+        .await
+        .expect("the connection was left open")
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn max_streams() {
+    let mut server = app();
+    server.keep_alive(Duration::from_secs(5), 2);
+    let (mut client, conn) = connect(server).await;
+
+    for _ in 0..2 {
+        let (resp, _) = client.send_request(get(), true).unwrap();
+        assert_eq!(resp.await.unwrap().status(), 200);
+    }
+    // no more streams are accepted past the max
+    let third = match client.send_request(get(), true) {
+        Ok((resp, _)) => resp.await.map(|_| ()),
+        Err(e) => Err(e),
+    };
+    assert!(third.is_err());
+
+    timeout(Duration::from_secs(5), conn)
+        .await
+        .expect("the connection was left open")
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn default_response() {
+    let mut server = app();
+    server.wrap(Maintenance);
+    let (mut client, _) = connect(server).await;
+
+    let (resp, _) = client.send_request(get(), true).unwrap();
+    assert_eq!(resp.await.unwrap().status(), 200);
+}
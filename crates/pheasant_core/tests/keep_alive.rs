@@ -0,0 +1,159 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{server, service};
+use pheasant_core::{Method, Server};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{sleep, timeout};
+
+// the shared test server with the services of these tests
+fn app() -> Server {
+    let mut server = server();
+    server
+        .service(|| service(Method::Get, "/hello"))
+        .service(|| service(Method::Post, "/upload"));
+
+    server
+}
+
+// writes the raw bytes to a single connection, pausing between the writes,
+// and reads what the server sent until it closed the connection
+async fn converse(mut server: Server, writes: &[&str], pause: Duration) -> String {
+    let addr = server.local_addr().unwrap();
+    let shutdown = server.shutdown_handle();
+    let serving = tokio::spawn(async move { server.serve().await });
+
+    let mut tcp = TcpStream::connect(addr).await.unwrap();
+    for write in writes {
+        tcp.write_all(write.as_bytes()).await.unwrap();
+        sleep(pause).await;
+    }
+    let mut resp = vec![];
+    // the server has to close the connection on its own
+    timeout(Duration::from_secs(5), tcp.read_to_end(&mut resp))
+        .await
+        .expect("the connection was left open")
+        .unwrap();
+
+    shutdown.shutdown();
+    serving.await.unwrap();
+
+    String::from_utf8_lossy(&resp).into_owned()
+}
+
+fn count(resps: &str, pat: &str) -> usize {
+    resps.matches(pat).count()
+}
+
+#[tokio::test]
+async fn persistent() {
+    let resps = converse(
+        app(),
+        &[
+            "GET /hello HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            "GET /hello HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            "GET /hello HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        ],
+        Duration::ZERO,
+    )
+    .await;
+
+    assert_eq!(count(&resps, "HTTP/1.1 200"), 3, "{}", resps);
+    assert_eq!(count(&resps, "Connection: keep-alive\n"), 2, "{}", resps);
+    assert_eq!(count(&resps, "Keep-Alive: timeout=5, max=100\n"), 2);
+    assert_eq!(count(&resps, "Connection: close\n"), 1, "{}", resps);
+}
+
+#[tokio::test]
+async fn http_1_0() {
+    // http 1.0 connections close after a single request unless asked otherwise
+    let resps = converse(
+        app(),
+        &[
+            "GET /hello HTTP/1.0\r\nHost: localhost\r\n\r\n",
+            "GET /hello HTTP/1.0\r\nHost: localhost\r\n\r\n",
+        ],
+        Duration::ZERO,
+    )
+    .await;
+    assert_eq!(count(&resps, " 200 "), 1, "{}", resps);
+    assert!(resps.contains("Connection: close\n"), "{}", resps);
+
+    let resps = converse(
+        app(),
+        &[
+            "GET /hello HTTP/1.0\r\nHost: localhost\r\nConnection: keep-alive\r\n\r\n",
+            "GET /hello HTTP/1.0\r\nHost: localhost\r\n\r\n",
+        ],
+        Duration::ZERO,
+    )
+    .await;
+    assert_eq!(count(&resps, " 200 "), 2, "{}", resps);
+    assert_eq!(count(&resps, "Connection: keep-alive\n"), 1, "{}", resps);
+}
+
+#[tokio::test]
+async fn max_requests() {
+    let mut server = app();
+    server.keep_alive(Duration::from_secs(5), 2);
+    // sent at once, a request left unread when the server closes would reset the connection
+    let reqs = "GET /hello HTTP/1.1\r\nHost: localhost\r\n\r\n".repeat(3);
+    let resps = converse(server, &[&reqs], Duration::ZERO).await;
+
+    // the connection closes after the second one
+    assert_eq!(count(&resps, "HTTP/1.1 200"), 2, "{}", resps);
+    assert_eq!(count(&resps, "Connection: close\n"), 1, "{}", resps);
+}
+
+#[tokio::test]
+async fn idle_timeout() {
+    let mut server = app();
+    server.keep_alive(Duration::from_millis(200), 100);
+    let resps = converse(
+        server,
+        &["GET /hello HTTP/1.1\r\nHost: localhost\r\n\r\n"],
+        Duration::ZERO,
+    )
+    .await;
+
+    assert_eq!(count(&resps, "HTTP/1.1 200"), 1, "{}", resps);
+    assert!(resps.contains("Connection: keep-alive\n"), "{}", resps);
+}
+
+#[tokio::test]
+async fn slow_upload() {
+    // the body takes longer than the timeout to arrive, but never stalls past it
+    let mut server = app();
+    server.keep_alive(Duration::from_millis(300), 100);
+    let resps = converse(
+        server,
+        &[
+            "POST /upload HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n",
+            "Content-Length: 6\r\n\r\n",
+            "he",
+            "ll",
+            "o!",
+        ],
+        Duration::from_millis(150),
+    )
+    .await;
+
+    assert!(resps.starts_with("HTTP/1.1 200"), "{}", resps);
+}
+
+#[tokio::test]
+async fn stalled_request() {
+    let mut server = app();
+    server.keep_alive(Duration::from_millis(200), 100);
+    let resps = converse(
+        server,
+        &["POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: 6\r\n\r\nhel"],
+        Duration::ZERO,
+    )
+    .await;
+
+    assert!(resps.starts_with("HTTP/1.1 408"), "{}", resps);
+    assert!(resps.contains("Connection: close\n"), "{}", resps);
+}
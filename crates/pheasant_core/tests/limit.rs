@@ -0,0 +1,105 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{send, service};
+use pheasant_core::{Load, Method, Server};
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tokio::time::sleep;
+
+const GET: &[u8] = b"GET /hello HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+
+// waits for the server to see the connections
+async fn settle(load: &Load, in_flight: usize, queued: usize) {
+    while load.in_flight() != in_flight || load.queued() != queued {
+        sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[tokio::test]
+async fn refused() {
+    let mut server = Server::new([127, 0, 0, 1], 0, 1).unwrap();
+    server.service(|| service(Method::Get, "/hello"));
+    let (addr, load) = (server.local_addr().unwrap(), server.load());
+    let shutdown = server.shutdown_handle();
+    let serving = tokio::spawn(async move { server.serve().await });
+
+    // holds the only slot
+    let idle = TcpStream::connect(addr).await.unwrap();
+    settle(&load, 1, 0).await;
+
+    let refused = send(addr, GET).await;
+    assert!(refused.starts_with("HTTP/1.1 503"), "{}", refused);
+    assert!(refused.contains("Connection: close\n"), "{}", refused);
+
+    drop(idle);
+    settle(&load, 0, 0).await;
+    let served = send(addr, GET).await;
+    assert!(served.starts_with("HTTP/1.1 200"), "{}", served);
+
+    shutdown.shutdown();
+    serving.await.unwrap();
+}
+
+#[tokio::test]
+async fn backlog() {
+    let mut server = Server::new([127, 0, 0, 1], 0, 1).unwrap();
+    server.backlog(1).service(|| service(Method::Get, "/hello"));
+    let (addr, load) = (server.local_addr().unwrap(), server.load());
+    let shutdown = server.shutdown_handle();
+    let serving = tokio::spawn(async move { server.serve().await });
+
+    let idle = TcpStream::connect(addr).await.unwrap();
+    settle(&load, 1, 0).await;
+    // waits in the backlog for the slot
+    let queued = tokio::spawn(send(addr, GET));
+    settle(&load, 1, 1).await;
+
+    // the backlog is full
+    let refused = send(addr, GET).await;
+    assert!(refused.starts_with("HTTP/1.1 503"), "{}", refused);
+
+    drop(idle);
+    let served = queued.await.unwrap();
+    assert!(served.starts_with("HTTP/1.1 200"), "{}", served);
+
+    shutdown.shutdown();
+    serving.await.unwrap();
+}
+
+#[tokio::test]
+async fn refusals() {
+    let mut server = Server::new([127, 0, 0, 1], 0, 1).unwrap();
+    server.service(|| service(Method::Get, "/hello"));
+    let (addr, load) = (server.local_addr().unwrap(), server.load());
+    let shutdown = server.shutdown_handle();
+    let serving = tokio::spawn(async move { server.serve().await });
+
+    let idle = TcpStream::connect(addr).await.unwrap();
+    settle(&load, 1, 0).await;
+    // gets its 503 but keeps the connection open, the server waits for it to close
+    let mut lingering = TcpStream::connect(addr).await.unwrap();
+    let mut resp = vec![];
+    lingering.read_to_end(&mut resp).await.unwrap();
+    assert!(resp.starts_with(b"HTTP/1.1 503"));
+    assert_eq!(load.refusing(), 1);
+
+    // past the refusals cap the connection is closed without a response
+    // closing with the request unread may reset it
+    let mut dropped = TcpStream::connect(addr).await.unwrap();
+    let mut resp = vec![];
+    _ = dropped.read_to_end(&mut resp).await;
+    assert!(resp.is_empty());
+
+    drop(lingering);
+    while load.refusing() != 0 {
+        sleep(Duration::from_millis(10)).await;
+    }
+    let refused = send(addr, GET).await;
+    assert!(refused.starts_with("HTTP/1.1 503"), "{}", refused);
+
+    drop(idle);
+    shutdown.shutdown();
+    serving.await.unwrap();
+}
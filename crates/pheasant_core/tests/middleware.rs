@@ -0,0 +1,217 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::{request, server};
+use pheasant_core::middleware::BoxFuture;
+use pheasant_core::{HeaderMap, Method, Middleware, Protocol, Request, Response, Scope, Service};
+use pheasant_uri::Route;
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+// records when its hooks run
+struct Trace {
+    name: &'static str,
+    log: Log,
+}
+
+impl Middleware for Trace {
+    fn before<'a>(&'a self, _: &'a mut Request) -> BoxFuture<'a, Option<Response>> {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("{} before", self.name));
+
+        Box::pin(async { None })
+    }
+
+    fn after<'a>(&'a self, _: &'a Request, _: &'a mut Response) -> BoxFuture<'a, ()> {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("{} after", self.name));
+
+        Box::pin(async {})
+    }
+}
+
+// answers the requests without a token
+struct Auth;
+
+impl Middleware for Auth {
+    fn before<'a>(&'a self, req: &'a mut Request) -> BoxFuture<'a, Option<Response>> {
+        Box::pin(async move {
+            match req.header::<String>("Authorization") {
+                Some(token) => {
+                    req.set_header("X-User", token);
+
+                    None
+                }
+                None => Some(Response::with_status(401)),
+            }
+        })
+    }
+
+    fn after<'a>(&'a self, _: &'a Request, resp: &'a mut Response) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            resp.set_header::<String>("X-Auth", "checked".into());
+        })
+    }
+}
+
+// answers every request with a response it didn't give a status to
+struct Maintenance;
+
+impl Middleware for Maintenance {
+    fn before<'a>(&'a self, _: &'a mut Request) -> BoxFuture<'a, Option<Response>> {
+        Box::pin(async {
+            let mut resp = Response::default();
+            resp.set_header::<String>("X-Maintenance", "on".into());
+
+            Some(resp)
+        })
+    }
+}
+
+struct User(Option<String>);
+
+impl From<&Request> for User {
+    fn from(req: &Request) -> Self {
+        Self(req.header::<String>("X-User"))
+    }
+}
+
+async fn user(user: User, proto: Protocol) -> Response {
+    let mut resp = Response::with_proto(proto);
+    resp.set_header("X-Served", user.0.unwrap_or_default());
+
+    resp
+}
+
+fn service(route: &str) -> Service {
+    Service::new(
+        Method::Get,
+        Route::macro_checked(route),
+        None,
+        None,
+        None,
+        user,
+    )
+}
+
+fn trace(name: &'static str, log: &Log) -> Trace {
+    Trace {
+        name,
+        log: log.clone(),
+    }
+}
+
+#[tokio::test]
+async fn order() {
+    let log = Log::default();
+
+    let mut inner = Scope::new("/inner");
+    inner.wrap(trace("inner", &log)).service(|| {
+        let mut service = service("/users");
+        service
+            .wrap(trace("service 1", &log))
+            .wrap(trace("service 2", &log));
+
+        service
+    });
+    let mut outer = Scope::new("/outer");
+    outer.wrap(trace("outer", &log)).scope(inner);
+
+    let mut server = server();
+    server
+        .wrap(trace("server 1", &log))
+        .mount(outer)
+        .wrap(trace("server 2", &log));
+    request(server, &["GET /outer/inner/users HTTP/1.1"]).await;
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "server 1 before",
+            "server 2 before",
+            "outer before",
+            "inner before",
+            "service 1 before",
+            "service 2 before",
+            "service 2 after",
+            "service 1 after",
+            "inner after",
+            "outer after",
+            "server 2 after",
+            "server 1 after",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn short_circuit() {
+    let log = Log::default();
+
+    let mut server = server();
+    server.wrap(trace("server", &log)).service(|| {
+        let mut service = service("/users");
+        service.wrap(Auth).wrap(trace("service", &log));
+
+        service
+    });
+    let resps = request(
+        server,
+        &[
+            "GET /users HTTP/1.1",
+            "GET /users HTTP/1.1\r\nAuthorization: alice",
+        ],
+    )
+    .await;
+    let (denied, allowed) = (&resps[0], &resps[1]);
+
+    assert!(denied.starts_with("HTTP/1.1 401"), "{}", denied);
+    // the layers that ran before the answer still see the response
+    assert!(denied.contains("X-Auth: checked\n"), "{}", denied);
+    assert!(!denied.contains("X-Served"), "{}", denied);
+
+    assert!(allowed.starts_with("HTTP/1.1 200"), "{}", allowed);
+    // the service sees the modified request
+    assert!(allowed.contains("X-Served: alice\n"), "{}", allowed);
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "server before",
+            "server after",
+            "server before",
+            "service before",
+            "service after",
+            "server after",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn unhandled_requests() {
+    let log = Log::default();
+
+    let mut server = server();
+    server
+        .wrap(trace("server", &log))
+        .service(|| service("/users"));
+    request(server, &["GET /missing HTTP/1.1"]).await;
+
+    // server layers see every request, even the ones without a service
+    assert_eq!(*log.lock().unwrap(), ["server before", "server after"]);
+}
+
+#[tokio::test]
+async fn default_response() {
+    let mut server = server();
+    server.wrap(Maintenance).service(|| service("/users"));
+    let resps = request(server, &["GET /users HTTP/1.1"]).await;
+
+    // sent as a 200 rather than failing on the missing status
+    assert!(resps[0].starts_with("HTTP/1.1 200"), "{}", resps[0]);
+    assert!(resps[0].contains("X-Maintenance: on\n"), "{}", resps[0]);
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{request, server, service};
+use pheasant_core::{Failure, Method, Response, Server};
+
+async fn not_allowed() -> Response {
+    let mut resp = Response::with_status(405);
+    resp.update_body(b"not allowed".to_vec());
+
+    resp
+}
+
+// the shared test server with the services of these tests
+fn app() -> Server {
+    let mut server = server();
+    server
+        .service(|| service(Method::Post, "/users/:id"))
+        .service(|| service(Method::Get, "/users/:id"))
+        .service(|| service(Method::Delete, "/users/me"));
+
+    server
+}
+
+#[test]
+fn allowed_methods() {
+    let server = app();
+
+    assert_eq!(
+        server.allowed_methods("/users/42"),
+        [Method::Head, Method::Get, Method::Post]
+    );
+    assert_eq!(
+        server.allowed_methods("/users/me"),
+        [Method::Head, Method::Get, Method::Post, Method::Delete]
+    );
+    assert!(server.allowed_methods("/posts/42").is_empty());
+
+    assert!(server.service_status(Method::Put, "/users/42").is_err());
+}
+
+#[tokio::test]
+async fn method_not_allowed() {
+    let resp = &request(app(), &["PUT /users/42 HTTP/1.1"]).await[0];
+
+    assert!(resp.starts_with("HTTP/1.1 405"), "{}", resp);
+    assert!(resp.contains("Allow: HEAD, GET, POST\n"), "{}", resp);
+}
+
+#[tokio::test]
+async fn registered_failure() {
+    let mut server = app();
+    server.error(|| Failure::new(405, None, not_allowed));
+    let resp = &request(server, &["PATCH /users/me HTTP/1.1"]).await[0];
+
+    assert!(resp.starts_with("HTTP/1.1 405"), "{}", resp);
+    assert!(
+        resp.contains("Allow: HEAD, GET, POST, DELETE\n"),
+        "{}",
+        resp
+    );
+}
+
+#[tokio::test]
+async fn not_found() {
+    let resp = &request(app(), &["PUT /posts/42 HTTP/1.1"]).await[0];
+
+    assert!(!resp.starts_with("HTTP/1.1 405"), "{}", resp);
+    assert!(!resp.contains("Allow:"), "{}", resp);
+}
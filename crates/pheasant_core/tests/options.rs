@@ -0,0 +1,156 @@
+mod common;
+
+use common::{request, server, service};
+use pheasant_core::{Cors, HeaderMap, Method, Protocol, Response, Server, Service};
+use pheasant_uri::{OriginSet, Route};
+
+async fn options(_: (), proto: Protocol) -> Response {
+    let mut resp = Response::with_proto(proto);
+    resp.set_header::<String>("X-Options", "service".into());
+
+    resp
+}
+
+fn cors(methods: &[Method], origins: &str) -> Cors {
+    let mut cors = Cors::new();
+    cors.methods().extend(methods);
+    cors.headers().insert("Content-Type".into());
+    cors.overwrite_origins(origins.parse::<OriginSet>().unwrap());
+
+    cors
+}
+
+// the shared test server with the services of these tests
+fn app() -> Server {
+    let mut server = server();
+    server
+        .service(|| service(Method::Get, "/users/:id"))
+        .service(|| service(Method::Post, "/users"));
+
+    server
+}
+
+#[tokio::test]
+async fn server_options() {
+    let mut server = app();
+    server.cors(cors(&[Method::Get, Method::Post], "*"));
+    let resps = request(
+        server,
+        &[
+            "OPTIONS * HTTP/1.1\r\nOrigin: http://app.example",
+            "GET * HTTP/1.1\r\nOrigin: http://app.example",
+        ],
+    )
+    .await;
+    let (options, get) = (&resps[0], &resps[1]);
+
+    assert!(options.starts_with("HTTP/1.1 200"), "{}", options);
+    assert!(
+        options.contains("Allow: HEAD, GET, POST, OPTIONS\n"),
+        "{}",
+        options
+    );
+    assert!(
+        options.contains("Access-Control-Allow-Origin: http://app.example\n"),
+        "{}",
+        options
+    );
+    // only OPTIONS can target the whole server
+    assert!(get.starts_with("HTTP/1.1 400"), "{}", get);
+}
+
+#[tokio::test]
+async fn default_preflight() {
+    let mut server = app();
+    server.cors(cors(&[Method::Get], "http://app.example"));
+    let resps = request(
+        server,
+        &[
+            "OPTIONS /users/42 HTTP/1.1\r\nAccess-Control-Request-Method: GET\r\nOrigin: http://app.example",
+            "GET /users/42 HTTP/1.1\r\nOrigin: http://app.example",
+            "OPTIONS /posts/42 HTTP/1.1\r\nAccess-Control-Request-Method: GET\r\nOrigin: http://app.example",
+        ],
+    )
+    .await;
+    let (preflight, get, missing) = (&resps[0], &resps[1], &resps[2]);
+
+    assert!(preflight.starts_with("HTTP/1.1 204"), "{}", preflight);
+    assert!(
+        preflight.contains("Access-Control-Allow-Origin: http://app.example\n"),
+        "{}",
+        preflight
+    );
+    assert!(
+        preflight.contains("Access-Control-Allow-Methods: GET\n"),
+        "{}",
+        preflight
+    );
+    // the actual request carries the cors headers too
+    assert!(
+        get.contains("Access-Control-Allow-Origin: http://app.example\n"),
+        "{}",
+        get
+    );
+    assert!(!missing.starts_with("HTTP/1.1 204"), "{}", missing);
+}
+
+#[tokio::test]
+async fn own_preflight_first() {
+    let mut server = app();
+    server.cors(cors(&[Method::Get], "*")).service(|| {
+        Service::new(
+            Method::Options,
+            Route::macro_checked("/users/:id"),
+            None,
+            None,
+            None,
+            options,
+        )
+    });
+    let resps = request(
+        server,
+        &["OPTIONS /users/42 HTTP/1.1\r\nAccess-Control-Request-Method: GET\r\nOrigin: http://app.example"],
+    )
+    .await;
+
+    assert!(resps[0].contains("X-Options: service\n"), "{}", resps[0]);
+}
+
+#[tokio::test]
+async fn without_default() {
+    let server = app();
+    assert_eq!(
+        server.allowed_methods("/users/42"),
+        [Method::Head, Method::Get]
+    );
+
+    let resps = request(
+        server,
+        &[
+            "OPTIONS /users/42 HTTP/1.1\r\nAccess-Control-Request-Method: GET\r\nOrigin: http://app.example",
+            "OPTIONS * HTTP/1.1\r\nOrigin: http://app.example",
+        ],
+    )
+    .await;
+    let (preflight, options) = (&resps[0], &resps[1]);
+
+    assert!(preflight.starts_with("HTTP/1.1 405"), "{}", preflight);
+    assert!(options.starts_with("HTTP/1.1 200"), "{}", options);
+    assert!(
+        !options.contains("Access-Control-Allow-Origin"),
+        "{}",
+        options
+    );
+}
+
+#[test]
+fn allowed_methods() {
+    let mut server = app();
+    server.cors(cors(&[Method::Get], "*"));
+
+    assert_eq!(
+        server.allowed_methods("/users/42"),
+        [Method::Head, Method::Get, Method::Options]
+    );
+    assert!(server.allowed_methods("/posts/42").is_empty());
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use std::collections::HashSet;
+
+use common::{request, server};
+use pheasant_core::{Method, Protocol, Response, Service};
+use pheasant_uri::Route;
+
+// picks its own status
+async fn index(_: (), proto: Protocol) -> Response {
+    let mut resp = Response::with_status(200);
+    resp.update_proto(proto);
+
+    resp
+}
+
+#[tokio::test]
+async fn redirect_status() {
+    let mut server = server();
+    server.service(|| {
+        Service::new(
+            Method::Get,
+            Route::macro_checked("/index"),
+            Some(HashSet::from([Route::macro_checked("/")])),
+            None,
+            None,
+            index,
+        )
+    });
+    let resps = request(server, &["GET / HTTP/1.1", "GET /index HTTP/1.1"]).await;
+    let (redirect, index) = (&resps[0], &resps[1]);
+
+    // the redirect route keeps redirecting whatever status the service picked
+    assert!(redirect.starts_with("HTTP/1.1 303"), "{}", redirect);
+    assert!(redirect.contains("Location: /index\n"), "{}", redirect);
+    assert!(index.starts_with("HTTP/1.1 200"), "{}", index);
+}
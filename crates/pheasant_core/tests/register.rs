@@ -0,0 +1,183 @@
+mod common;
+
+use common::{redirecting, request, server, service};
+use pheasant_core::{Cors, Failure, Method, Protocol, RegisterError, Response, Service};
+use pheasant_uri::{OriginSet, Route};
+
+async fn user(_: (), proto: Protocol) -> Response {
+    Response::with_proto(proto)
+}
+
+async fn profile(_: (), proto: Protocol) -> Response {
+    Response::with_proto(proto)
+}
+
+async fn not_found() -> Response {
+    Response::with_status(404)
+}
+
+async fn missing() -> Response {
+    Response::with_status(404)
+}
+
+// a service of the route with its own cors policy, like the ones `#[cors]` generates
+fn cors_service(method: Method, route: &str) -> Service {
+    let mut cors = Cors::new();
+    cors.methods().insert(method);
+    cors.overwrite_origins("http://app.example".parse::<OriginSet>().unwrap());
+
+    Service::new(
+        method,
+        Route::macro_checked(route),
+        None,
+        None,
+        Some(cors),
+        user,
+    )
+}
+
+#[test]
+fn same_route() {
+    let mut server = server();
+    server.service(|| service(Method::Get, "/users/:id"));
+
+    let err = server
+        .try_service(|| {
+            Service::new(
+                Method::Get,
+                Route::macro_checked("/users/{name}"),
+                None,
+                None,
+                None,
+                profile,
+            )
+        })
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err,
+        RegisterError::Conflict {
+            method: Method::Get,
+            ..
+        }
+    ));
+    // both definitions are named
+    let msg = err.to_string();
+    assert!(
+        msg.contains("/users/:id (register::common::hello)"),
+        "{}",
+        msg
+    );
+    assert!(msg.contains("/users/{name} (register::profile)"), "{}", msg);
+
+    // other methods and more specific routes don't collide
+    assert!(
+        server
+            .try_service(|| service(Method::Post, "/users/:id"))
+            .is_ok()
+    );
+    assert!(
+        server
+            .try_service(|| service(Method::Get, "/users/me"))
+            .is_ok()
+    );
+}
+
+#[test]
+fn bundle() {
+    let mut server = server();
+    let err = server
+        .try_service(|| {
+            [
+                service(Method::Get, "/a"),
+                service(Method::Get, "/b"),
+                service(Method::Get, "/a"),
+            ]
+        })
+        .err();
+    assert!(matches!(err, Some(RegisterError::Conflict { .. })));
+
+    // none of the bundle got registered
+    assert!(server.service_status(Method::Get, "/b").is_err());
+}
+
+#[test]
+fn redirects() {
+    let mut server = server();
+    server.service(|| redirecting(Method::Get, "/new", &["/old"]));
+
+    // the same redirect for the same method
+    let err = server.try_service(|| redirecting(Method::Get, "/newer", &["/old"]));
+    assert!(matches!(err, Err(RegisterError::Redirect { ref route, .. }) if route == "/old"));
+    // a redirect that a route already answers
+    let err = server.try_service(|| redirecting(Method::Get, "/newer", &["/new"]));
+    assert!(matches!(err, Err(RegisterError::Redirect { .. })));
+    // a route that matches a redirect
+    let err = server.try_service(|| service(Method::Get, "/:page"));
+    assert!(matches!(err, Err(RegisterError::Redirect { .. })));
+    // within a bundle
+    let err = server.try_service(|| {
+        [
+            redirecting(Method::Put, "/x", &["/y"]),
+            redirecting(Method::Put, "/z", &["/y"]),
+        ]
+    });
+    assert!(matches!(err, Err(RegisterError::Redirect { .. })));
+
+    assert!(
+        server
+            .try_service(|| redirecting(Method::Post, "/newer", &["/old"]))
+            .is_ok()
+    );
+}
+
+#[tokio::test]
+async fn cors_services() {
+    let mut server = server();
+    // the preflights of both are answered by the server, they don't collide
+    server
+        .service(|| cors_service(Method::Get, "/x"))
+        .service(|| cors_service(Method::Post, "/x"));
+    let resps = request(
+        server,
+        &["OPTIONS /x HTTP/1.1\r\nOrigin: http://app.example\r\nAccess-Control-Request-Method: POST"],
+    )
+    .await;
+    let preflight = &resps[0];
+
+    assert!(preflight.starts_with("HTTP/1.1 204"), "{}", preflight);
+    assert!(
+        preflight.contains("Access-Control-Allow-Methods: POST\n"),
+        "{}",
+        preflight
+    );
+}
+
+#[test]
+fn failures() {
+    let mut server = server();
+    server.error(|| Failure::new(404, None, not_found));
+
+    let err = server
+        .try_error(|| Failure::new(404, None, missing))
+        .err()
+        .unwrap();
+    assert!(matches!(err, RegisterError::Failure { code: 404, .. }));
+    let msg = err.to_string();
+    assert!(msg.contains("register::not_found"), "{}", msg);
+    assert!(msg.contains("register::missing"), "{}", msg);
+
+    assert!(
+        server
+            .try_error(|| Failure::new(500, None, missing))
+            .is_ok()
+    );
+}
+
+#[test]
+#[should_panic(expected = "conflicts with the already registered")]
+fn panics() {
+    server()
+        .service(|| service(Method::Get, "/a"))
+        .service(|| service(Method::Get, "/a"));
+}
@@ -0,0 +1,52 @@
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+use pheasant_core::ReloadableTls;
+use pheasant_core::tls::{DevCa, KeyType};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, timeout};
+
+// the result of the next reload
+async fn reloaded(rx: &mut mpsc::UnboundedReceiver<bool>) -> bool {
+    timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn watch() {
+    let dir = std::env::temp_dir().join(format!("pheasant-reload-{}", std::process::id()));
+    let ca = DevCa::generate("test ca", KeyType::EcdsaP256, 1).unwrap();
+    let (first, second) = (
+        ca.issue(&["localhost".into()], KeyType::EcdsaP256, 1)
+            .unwrap(),
+        ca.issue(&["localhost".into()], KeyType::EcdsaP256, 1)
+            .unwrap(),
+    );
+    first.write(&dir, "leaf").unwrap();
+    let (cert, key) = (dir.join("leaf.pem"), dir.join("leaf.key"));
+
+    let tls = ReloadableTls::from_pem_files(&cert, &key).unwrap();
+    let config = tls.config();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let watching = tls.watch(Duration::from_millis(10), move |res| {
+        _ = tx.send(res.is_ok());
+    });
+    // lets the watcher take note of the files first
+    sleep(Duration::from_millis(50)).await;
+
+    // a broken key fails the reload, the old config is kept
+    fs::write(&key, "not a key").unwrap();
+    assert!(!reloaded(&mut rx).await);
+    assert!(Arc::ptr_eq(&config, &tls.config()));
+
+    // the files are written one after the other, a reload may see only the first
+    second.write(&dir, "leaf").unwrap();
+    while !reloaded(&mut rx).await {}
+    assert!(!Arc::ptr_eq(&config, &tls.config()));
+
+    watching.abort();
+    _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use std::collections::HashSet;
+
+use common::{hello, request, server, service};
+use pheasant_core::{Cors, Method, RegisterError, Scope, Service};
+use pheasant_uri::{OriginSet, Route};
+
+fn cors(origins: &str) -> Cors {
+    let mut cors = Cors::new();
+    cors.methods().insert(Method::Get);
+    cors.overwrite_origins(origins.parse::<OriginSet>().unwrap());
+
+    cors
+}
+
+fn api() -> Scope {
+    let mut users = Scope::new("/users/");
+    users.service(|| service(Method::Get, "/")).service(|| {
+        [
+            service(Method::Get, "/:id"),
+            service(Method::Delete, "/:id"),
+        ]
+    });
+
+    let mut v1 = Scope::new("v1");
+    v1.service(|| service(Method::Get, "/status")).scope(users);
+
+    let mut api = Scope::new("/api");
+    api.scope(v1);
+
+    api
+}
+
+#[test]
+fn prefixes() {
+    let api = api();
+    assert_eq!(api.prefix(), "/api");
+    assert_eq!(api.len(), 4);
+
+    let mut server = server();
+    server.mount(api);
+
+    for (method, path, route) in [
+        (Method::Get, "/api/v1/status", "/api/v1/status"),
+        (Method::Get, "/api/v1/users", "/api/v1/users"),
+        (Method::Get, "/api/v1/users/42", "/api/v1/users/:id"),
+        (Method::Delete, "/api/v1/users/42", "/api/v1/users/:id"),
+    ] {
+        let (_, service) = server.service_status(method, path).unwrap();
+        assert_eq!(service.route(), route);
+    }
+    assert!(server.service_status(Method::Get, "/users/42").is_err());
+    assert!(server.service_status(Method::Get, "/api/status").is_err());
+}
+
+#[test]
+fn redirects() {
+    let mut scope = Scope::new("/docs");
+    scope.service(|| {
+        Service::new(
+            Method::Get,
+            Route::macro_checked("/index"),
+            Some(HashSet::from([Route::macro_checked("/")])),
+            None,
+            None,
+            hello,
+        )
+    });
+
+    let mut server = server();
+    server.mount(scope);
+
+    let (_, service) = server.service_status(Method::Get, "/docs").unwrap();
+    assert_eq!(service.route(), "/docs/index");
+}
+
+#[test]
+fn collisions() {
+    let mut server = server();
+    server.service(|| service(Method::Get, "/api/v1/users/:name"));
+
+    let err = server.try_mount(api()).err();
+    assert!(
+        matches!(err, Some(RegisterError::Conflict { .. })),
+        "{:?}",
+        err
+    );
+    // none of the scope services were registered
+    assert!(
+        server
+            .service_status(Method::Get, "/api/v1/status")
+            .is_err()
+    );
+}
+
+#[test]
+fn bundles() {
+    let mut server = server();
+    server.service(|| {
+        [
+            vec![service(Method::Get, "/a"), service(Method::Post, "/a")],
+            vec![service(Method::Get, "/b")],
+        ]
+    });
+    server.service(|| {
+        [
+            service(Method::Get, "/c"),
+            service(Method::Get, "/d"),
+            service(Method::Get, "/e"),
+            service(Method::Get, "/f"),
+        ]
+    });
+    server.service(api);
+
+    for path in ["/a", "/b", "/c", "/f", "/api/v1/status"] {
+        assert!(server.service_status(Method::Get, path).is_ok(), "{}", path);
+    }
+}
+
+#[tokio::test]
+async fn defaults() {
+    let mut inner = Scope::new("/inner");
+    inner
+        .mime("text/plain".parse().unwrap())
+        .service(|| service(Method::Get, "/hello"));
+
+    let mut outer = Scope::new("/outer");
+    outer
+        .mime("application/json".parse().unwrap())
+        .cors(cors("http://app.example"))
+        .service(|| service(Method::Get, "/hello"))
+        .scope(inner);
+
+    let mut server = server();
+    server.mount(outer);
+    let resps = request(
+        server,
+        &[
+            "GET /outer/hello HTTP/1.1\r\nOrigin: http://app.example",
+            "GET /outer/inner/hello HTTP/1.1\r\nOrigin: http://app.example",
+            "OPTIONS /outer/inner/hello HTTP/1.1\r\nOrigin: http://app.example\r\nAccess-Control-Request-Method: GET",
+        ],
+    )
+    .await;
+
+    let (outer, inner, preflight) = (&resps[0], &resps[1], &resps[2]);
+    assert!(
+        outer.contains("Content-Type: application/json\n"),
+        "{}",
+        outer
+    );
+    assert!(inner.contains("Content-Type: text/plain\n"), "{}", inner);
+    // the outer scope cors applies to the inner services too
+    for resp in [outer, inner, preflight] {
+        assert!(
+            resp.contains("Access-Control-Allow-Origin: http://app.example\n"),
+            "{}",
+            resp
+        );
+    }
+    assert!(preflight.starts_with("HTTP/1.1 204"), "{}", preflight);
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use common::server;
+use pheasant_core::{Method, Protocol, Response, Service};
+use pheasant_uri::Route;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::sleep;
+
+async fn quick(_: (), proto: Protocol) -> Response {
+    sleep(Duration::from_millis(300)).await;
+
+    Response::with_proto(proto)
+}
+
+async fn stalled(_: (), proto: Protocol) -> Response {
+    sleep(Duration::from_secs(30)).await;
+
+    Response::with_proto(proto)
+}
+
+// sends the request and reads whatever the server sends back before closing the connection
+async fn request(tcp: &mut TcpStream, route: &str) -> String {
+    let req = format!(
+        "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        route
+    );
+    tcp.write_all(req.as_bytes()).await.unwrap();
+    let mut resp = vec![];
+    // an aborted connection may be reset rather than closed
+    _ = tcp.read_to_end(&mut resp).await;
+
+    String::from_utf8_lossy(&resp).into_owned()
+}
+
+#[tokio::test]
+async fn grace_period() {
+    let mut server = server();
+    server
+        .grace_period(Duration::from_secs(1))
+        .service(|| {
+            Service::new(
+                Method::Get,
+                Route::macro_checked("/quick"),
+                None,
+                None,
+                None,
+                quick,
+            )
+        })
+        .service(|| {
+            Service::new(
+                Method::Get,
+                Route::macro_checked("/stalled"),
+                None,
+                None,
+                None,
+                stalled,
+            )
+        });
+    let (addr, load) = (server.local_addr().unwrap(), server.load());
+    let shutdown = server.shutdown_handle();
+    let serving = tokio::spawn(async move { server.serve().await });
+
+    let (mut a, mut b) = (
+        TcpStream::connect(addr).await.unwrap(),
+        TcpStream::connect(addr).await.unwrap(),
+    );
+    let quick = tokio::spawn(async move { request(&mut a, "/quick").await });
+    let stalled = tokio::spawn(async move { request(&mut b, "/stalled").await });
+    // both requests are in flight when the shutdown starts
+    while load.in_flight() < 2 {
+        sleep(Duration::from_millis(10)).await;
+    }
+    sleep(Duration::from_millis(100)).await;
+
+    let start = Instant::now();
+    shutdown.shutdown();
+    serving.await.unwrap();
+
+    // the server returns once the grace period is over, not when the stalled request is done
+    assert!(
+        start.elapsed() < Duration::from_secs(5),
+        "{:?}",
+        start.elapsed()
+    );
+    assert_eq!(load.in_flight(), 0);
+
+    let quick = quick.await.unwrap();
+    assert!(quick.starts_with("HTTP/1.1 200"), "{}", quick);
+    // the stalled connection was cut off without a response
+    let stalled = stalled.await.unwrap();
+    assert!(stalled.is_empty(), "{}", stalled);
+}
+
+#[tokio::test]
+async fn serve_again() {
+    let mut server = server();
+    server.service(|| {
+        Service::new(
+            Method::Get,
+            Route::macro_checked("/quick"),
+            None,
+            None,
+            None,
+            quick,
+        )
+    });
+    let addr = server.local_addr().unwrap();
+    let shutdown = server.shutdown_handle();
+
+    let serving = tokio::spawn(async move {
+        server.serve().await;
+
+        server
+    });
+    shutdown.shutdown();
+    let mut server = serving.await.unwrap();
+
+    // the socket is closed once the server stopped
+    assert!(TcpStream::connect(addr).await.is_err());
+
+    // it binds the same address again
+    let serving = tokio::spawn(async move { server.serve().await });
+    let mut tcp = loop {
+        match TcpStream::connect(addr).await {
+            Ok(tcp) => break tcp,
+            Err(_) => sleep(Duration::from_millis(10)).await,
+        }
+    };
+    let resp = request(&mut tcp, "/quick").await;
+    assert!(resp.starts_with("HTTP/1.1 200"), "{}", resp);
+
+    shutdown.shutdown();
+    serving.await.unwrap();
+}
+
+#[tokio::test]
+async fn taken_address() {
+    let mut server = server();
+    let errors = Arc::new(Mutex::new(vec![]));
+    let reported = errors.clone();
+    server.on_error(move |e| reported.lock().unwrap().push(e.to_string()));
+    let addr = server.local_addr().unwrap();
+
+    let shutdown = server.shutdown_handle();
+    shutdown.shutdown();
+    server.serve().await;
+    assert!(errors.lock().unwrap().is_empty());
+
+    // the address got taken while the server wasn't serving
+    let _taken = TcpListener::bind(addr).unwrap();
+    server.serve().await;
+    assert_eq!(errors.lock().unwrap().len(), 1);
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use common::server;
+use pheasant_core::tls::{DevCa, IssuedCert, KeyType, SniResolver};
+use rustls::crypto::ring::default_provider;
+use rustls::{ClientConfig, RootCertStore};
+use rustls_pki_types::{CertificateDer, ServerName};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+
+fn issue(ca: &DevCa, names: &[&str]) -> IssuedCert {
+    let names = names
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+
+    ca.issue(&names, KeyType::default(), 1).unwrap()
+}
+
+fn add(certs: &mut SniResolver, name: &str, cert: &IssuedCert) {
+    certs
+        .add(name, vec![cert.cert().clone()], cert.key())
+        .unwrap();
+}
+
+// runs the handshake for the name and returns the certificate the server picked
+async fn served(ca: &DevCa, addr: SocketAddr, name: &str) -> Option<CertificateDer<'static>> {
+    let mut roots = RootCertStore::empty();
+    roots.add(ca.cert().clone()).unwrap();
+    let config = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    let tcp = TcpStream::connect(addr).await.unwrap();
+    let name = ServerName::try_from(name.to_owned()).unwrap();
+    let tls = TlsConnector::from(Arc::new(config))
+        .connect(name, tcp)
+        .await
+        .ok()?;
+
+    tls.get_ref()
+        .1
+        .peer_certificates()
+        .map(|chain| chain[0].clone())
+}
+
+#[tokio::test]
+async fn resolution() {
+    let ca = DevCa::generate("test ca", KeyType::default(), 1).unwrap();
+    let exact = issue(&ca, &["www.example.com"]);
+    let wildcard = issue(&ca, &["*.example.com"]);
+    let default = issue(&ca, &["fallback.test", "deep.api.example.com"]);
+
+    let mut certs = SniResolver::new();
+    add(&mut certs, "www.example.com", &exact);
+    add(&mut certs, "*.Example.com", &wildcard);
+    certs
+        .default_cert(vec![default.cert().clone()], default.key())
+        .unwrap();
+
+    let mut server = server();
+    let addr = server.local_addr().unwrap();
+    let shutdown = server.shutdown_handle();
+    server.tls(certs.into_config().unwrap());
+    let serving = tokio::spawn(async move { server.serve().await });
+
+    // exact names win over the wildcards
+    assert_eq!(
+        served(&ca, addr, "www.example.com").await.as_ref(),
+        Some(exact.cert())
+    );
+    assert_eq!(
+        served(&ca, addr, "api.example.com").await.as_ref(),
+        Some(wildcard.cert())
+    );
+    // the names are matched regardless of their case
+    assert_eq!(
+        served(&ca, addr, "API.example.COM").await.as_ref(),
+        Some(wildcard.cert())
+    );
+    // a wildcard covers a single label
+    assert_eq!(
+        served(&ca, addr, "deep.api.example.com").await.as_ref(),
+        Some(default.cert())
+    );
+    assert_eq!(
+        served(&ca, addr, "fallback.test").await.as_ref(),
+        Some(default.cert())
+    );
+
+    shutdown.shutdown();
+    serving.await.unwrap();
+}
+
+#[tokio::test]
+async fn without_default() {
+    let ca = DevCa::generate("test ca", KeyType::default(), 1).unwrap();
+    let wildcard = issue(&ca, &["*.example.com", "example.com"]);
+
+    let mut certs = SniResolver::new();
+    add(&mut certs, "*.example.com", &wildcard);
+
+    let mut server = server();
+    let addr = server.local_addr().unwrap();
+    let shutdown = server.shutdown_handle();
+    server.tls(certs.into_config().unwrap());
+    let serving = tokio::spawn(async move { server.serve().await });
+
+    assert_eq!(
+        served(&ca, addr, "api.example.com").await.as_ref(),
+        Some(wildcard.cert())
+    );
+    // the wildcard doesn't cover the domain itself, and there is nothing to fall back on
+    assert_eq!(served(&ca, addr, "example.com").await, None);
+
+    shutdown.shutdown();
+    serving.await.unwrap();
+}
+
+#[test]
+fn invalid_names() {
+    let ca = DevCa::generate("test ca", KeyType::default(), 1).unwrap();
+    let cert = issue(&ca, &["example.com"]);
+
+    let mut certs = SniResolver::new();
+    for name in [
+        "",
+        "*.",
+        "*.*.example.com",
+        "api.*.com",
+        "bad name.com",
+        "a..com",
+    ] {
+        assert!(
+            certs
+                .add(name, vec![cert.cert().clone()], cert.key())
+                .is_err(),
+            "{}",
+            name
+        );
+    }
+}
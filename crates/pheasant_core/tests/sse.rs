@@ -0,0 +1,113 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{request, server};
+use pheasant_core::{Event, Method, Protocol, Response, Service, Sse};
+use pheasant_uri::Route;
+use tokio::time::sleep;
+
+async fn events(_: (), _: Protocol) -> Response {
+    let (tx, sse) = Sse::channel(8);
+    for event in [
+        Event::data("tick"),
+        Event::data(""),
+        Event::data("first\nsecond\r\n").event("stats").id("42"),
+        Event::data("{}")
+            .event("multi\nline")
+            .retry(Duration::from_millis(1500)),
+    ] {
+        tx.try_send(event).unwrap();
+    }
+    // the stream ends once the queued events are sent
+
+    sse.no_heartbeat().into()
+}
+
+// an event every 40ms, then a pause longer than the heartbeat period
+async fn ticks(_: (), _: Protocol) -> Response {
+    let (tx, sse) = Sse::channel(8);
+    tokio::spawn(async move {
+        for _ in 0..8 {
+            sleep(Duration::from_millis(40)).await;
+            _ = tx.send(Event::data("tick")).await;
+        }
+        sleep(Duration::from_millis(250)).await;
+    });
+
+    sse.heartbeat(Duration::from_millis(100)).into()
+}
+
+// the frames of the chunked event stream body, one per chunk
+fn frames(resp: &str) -> Vec<&str> {
+    let (_, body) = resp.split_once("\n\n").unwrap();
+
+    body.split("\r\n")
+        .collect::<Vec<_>>()
+        .chunks(2)
+        .filter_map(|chunk| chunk.get(1).copied())
+        .filter(|frame| !frame.is_empty())
+        .collect()
+}
+
+#[tokio::test]
+async fn framing() {
+    let mut server = server();
+    server.service(|| {
+        Service::new(
+            Method::Get,
+            Route::macro_checked("/events"),
+            None,
+            None,
+            None,
+            events,
+        )
+    });
+    let resps = request(server, &["GET /events HTTP/1.1"]).await;
+    let resp = &resps[0];
+
+    assert!(resp.starts_with("HTTP/1.1 200"), "{}", resp);
+    assert!(
+        resp.contains("Content-Type: text/event-stream\n"),
+        "{}",
+        resp
+    );
+    assert!(resp.contains("Cache-Control: no-cache\n"), "{}", resp);
+    assert_eq!(
+        frames(resp),
+        [
+            "data: tick\n\n",
+            // empty data still makes a data line, or the client would drop the event
+            "data: \n\n",
+            "event: stats\ndata: first\ndata: second\ndata: \nid: 42\n\n",
+            "event: multiline\ndata: {}\nretry: 1500\n\n",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn heartbeat() {
+    let mut server = server();
+    server.service(|| {
+        Service::new(
+            Method::Get,
+            Route::macro_checked("/ticks"),
+            None,
+            None,
+            None,
+            ticks,
+        )
+    });
+    let resps = request(server, &["GET /ticks HTTP/1.1"]).await;
+    let frames = frames(&resps[0]);
+
+    // the events keep the heartbeats back, they only come during the pause
+    let (events, pause) = frames.split_at(8);
+    assert!(
+        events.iter().all(|frame| *frame == "data: tick\n\n"),
+        "{:?}",
+        frames
+    );
+    assert!(!pause.is_empty(), "{:?}", frames);
+    assert!(pause.iter().all(|frame| *frame == ":\n\n"), "{:?}", frames);
+}
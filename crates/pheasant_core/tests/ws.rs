@@ -0,0 +1,190 @@
+mod common;
+
+use common::server;
+use pheasant_core::{Message, Method, Protocol, Response, Service, WsUpgrade};
+use pheasant_uri::Route;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+const TEXT: u8 = 0x1;
+const CONTINUATION: u8 = 0x0;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xA;
+
+const MASK: [u8; 4] = [0x37, 0xfa, 0x21, 0x3d];
+
+// echoes the data messages back, the control frames are handled by the receiver
+async fn echo(ws: WsUpgrade, _: Protocol) -> Response {
+    ws.max_frame(8)
+        .max_message(16)
+        .on_upgrade(|mut ws| async move {
+            while let Some(msg) = ws.recv().await {
+                if let Message::Text(_) | Message::Binary(_) = msg {
+                    _ = ws.send(msg).await;
+                }
+            }
+        })
+}
+
+// a masked client frame
+fn masked(fin: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![(fin as u8) << 7 | opcode, 0x80 | payload.len() as u8];
+    frame.extend(MASK);
+    frame.extend(payload.iter().enumerate().map(|(idx, b)| b ^ MASK[idx % 4]));
+
+    frame
+}
+
+struct Client {
+    reader: BufReader<TcpStream>,
+}
+
+impl Client {
+    // opens a websocket connection to the server
+    async fn connect() -> Self {
+        let mut server = server();
+        server.service(|| {
+            Service::new(
+                Method::Get,
+                Route::macro_checked("/echo"),
+                None,
+                None,
+                None,
+                echo,
+            )
+        });
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(async move { server.serve().await });
+
+        let mut tcp = TcpStream::connect(addr).await.unwrap();
+        tcp.write_all(
+            b"GET /echo HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+            Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+        )
+        .await
+        .unwrap();
+
+        let mut reader = BufReader::new(tcp);
+        let mut head = String::new();
+        while !head.ends_with("\n\n") {
+            assert!(reader.read_line(&mut head).await.unwrap() > 0, "{}", head);
+        }
+        assert!(head.starts_with("HTTP/1.1 101"), "{}", head);
+        assert!(
+            head.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\n"),
+            "{}",
+            head
+        );
+
+        Self { reader }
+    }
+
+    async fn send(&mut self, frame: &[u8]) {
+        self.reader.get_mut().write_all(frame).await.unwrap();
+    }
+
+    // reads the next server frame, its first byte and its payload
+    async fn recv(&mut self) -> (u8, Vec<u8>) {
+        let mut head = [0; 2];
+        self.reader.read_exact(&mut head).await.unwrap();
+        // server frames are never masked
+        assert_eq!(head[1] & 0x80, 0);
+
+        let mut payload = vec![0; (head[1] & 0x7F) as usize];
+        self.reader.read_exact(&mut payload).await.unwrap();
+
+        (head[0], payload)
+    }
+
+    // reads the close frame the server sends and its status code
+    async fn closed(&mut self) -> u16 {
+        let (head, payload) = self.recv().await;
+        assert_eq!(head, 0x80 | CLOSE);
+
+        let code = u16::from_be_bytes([payload[0], payload[1]]);
+        // the server closes the connection after the close frame
+        let mut rest = vec![];
+        self.reader.read_to_end(&mut rest).await.unwrap();
+        assert!(rest.is_empty(), "{:?}", rest);
+
+        code
+    }
+}
+
+#[tokio::test]
+async fn masking() {
+    let mut client = Client::connect().await;
+    client.send(&masked(true, TEXT, b"hello")).await;
+
+    assert_eq!(client.recv().await, (0x80 | TEXT, b"hello".to_vec()));
+}
+
+#[tokio::test]
+async fn unmasked_frame() {
+    let mut client = Client::connect().await;
+    client.send(&[0x80 | TEXT, 2, b'h', b'i']).await;
+
+    assert_eq!(client.closed().await, 1002);
+}
+
+#[tokio::test]
+async fn fragmentation() {
+    let mut client = Client::connect().await;
+    client.send(&masked(false, TEXT, b"hel")).await;
+    // control frames can come between the fragments
+    client.send(&masked(true, PING, b"ping")).await;
+    client.send(&masked(false, CONTINUATION, b"lo")).await;
+    client.send(&masked(true, CONTINUATION, b", world")).await;
+
+    assert_eq!(client.recv().await, (0x80 | PONG, b"ping".to_vec()));
+    assert_eq!(client.recv().await, (0x80 | TEXT, b"hello, world".to_vec()));
+}
+
+#[tokio::test]
+async fn interleaved_message() {
+    let mut client = Client::connect().await;
+    client.send(&masked(false, TEXT, b"hel")).await;
+    // a new message can't start before the fragmented one is done
+    client.send(&masked(true, TEXT, b"lo")).await;
+
+    assert_eq!(client.closed().await, 1002);
+}
+
+#[tokio::test]
+async fn fragmented_control_frame() {
+    let mut client = Client::connect().await;
+    client.send(&masked(false, PING, b"ping")).await;
+
+    assert_eq!(client.closed().await, 1002);
+}
+
+#[tokio::test]
+async fn frame_too_big() {
+    let mut client = Client::connect().await;
+    client.send(&masked(true, TEXT, b"too long!")).await;
+
+    assert_eq!(client.closed().await, 1009);
+}
+
+#[tokio::test]
+async fn message_too_big() {
+    let mut client = Client::connect().await;
+    client.send(&masked(false, TEXT, b"12345678")).await;
+    client.send(&masked(false, CONTINUATION, b"12345678")).await;
+    // each frame fits, the whole message doesn't
+    client.send(&masked(true, CONTINUATION, b"1")).await;
+
+    assert_eq!(client.closed().await, 1009);
+}
+
+#[tokio::test]
+async fn close_handshake() {
+    let mut client = Client::connect().await;
+    let mut payload = 1000u16.to_be_bytes().to_vec();
+    payload.extend(b"bye");
+    client.send(&masked(true, CLOSE, &payload)).await;
+
+    // the server acknowledges with the same code
+    assert_eq!(client.closed().await, 1000);
+}
@@ -17,8 +17,8 @@ pub struct ServicePoet {
     // if the user service returns a Response then true
     // els if it returns Vec<u8> then false
     decorated: bool,
-    // if Some then an Options service corresponding to the user service has to be generated
-    // with the passed cors headers, service route and client request origin
+    // if Some then the service answers with the passed cors headers,
+    // its preflights are answered by the server with them too
     cors: Option<Cors>,
     // requesting any of the Routes in redirections triggers a redirection response towards
     // this service
@@ -143,11 +143,6 @@ pub trait ServiceInscriptions {
     // makes the fun that returns a Service bundle
     fn assemble_bundler_fun(&self) -> TS2;
 
-    // returns the proc_macro2::TokenStream value of this ServicePoet's preflight fun
-    // i.e., this implements an Options service for this ServicePoet's Route
-    // using the Cors at hand
-    fn assemble_preflight_fun(&self) -> TS2;
-
     fn assemble(&mut self) -> TS2;
 }
 
@@ -185,26 +180,6 @@ impl ServiceInscriptions for ServicePoet {
         }
     }
 
-    fn assemble_preflight_fun(&self) -> TS2 {
-        let method = Method::Options;
-        let fun = &self.fun;
-        let vis = fun.vis();
-        let preflight = fun.decorate_ident("_preflight");
-        let service = fun.decorate_ident("_preflight_service");
-        let route = self.route();
-        let cors = self.cors();
-
-        quote! {
-            #vis async fn #preflight(origin: pheasant::RequestOrigin, proto: pheasant::Protocol) -> pheasant::Response {
-                let mut resp = pheasant::Response::preflight(& #cors .unwrap(), origin.origin());
-                resp.update_status(pheasant::Status::Successful(pheasant::Successful::NoContent), None, None);
-                resp.update_proto(proto);
-
-                resp
-            }
-        }
-    }
-
     fn assemble_bundler_fun(&self) -> TS2 {
         let fun = &self.fun;
         let vis = fun.vis();
@@ -215,37 +190,12 @@ impl ServiceInscriptions for ServicePoet {
         let cors = self.cors();
         let method = self.method;
 
-        let preflight = fun.decorate_ident("_preflight");
-        let preflight = self.cors.is_some().then(|| {
-            service(
-                Method::Options,
-                &route,
-                &quote! { None },
-                &quote! {None},
-                &quote! { None },
-                &preflight,
-            )
-        });
         let decorated = fun.decorate_ident("_decorator");
         let decorated = service(method, &route, &re, &mime, &cors, &decorated);
-        let (return_type, service_bundle) = if self.cors.is_some() {
-            (
-                Type::Verbatim("[pheasant::Service; 2]".parse().unwrap()),
-                quote! {[
-                    #preflight,
-                    #decorated
-                ]},
-            )
-        } else {
-            (
-                Type::Verbatim("pheasant::Service".parse().unwrap()),
-                quote! {#decorated},
-            )
-        };
 
         quote! {
-            #vis fn #bundler() -> #return_type {
-                #service_bundle
+            #vis fn #bundler() -> pheasant::Service {
+                #decorated
             }
         }
     }
@@ -255,8 +205,6 @@ impl ServiceInscriptions for ServicePoet {
         let vis = fun.vis();
 
         let decorator_fun = self.assemble_decorator_fun();
-        let preflight_funs = self.cors.is_some().then(|| self.assemble_preflight_fun());
-        // TODO still doesnt bundle preflight service in
         // TODO too many redundant assignments in the functions assemblers
         // just assign everythin here and pass them by ref
         let bundler_fun = self.assemble_bundler_fun();
@@ -269,8 +217,6 @@ impl ServiceInscriptions for ServicePoet {
 
             #decorator_fun
 
-            #preflight_funs
-
             #bundler_fun
         }
     }
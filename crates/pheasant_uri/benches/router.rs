@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+use pheasant_uri::{Pattern, Router};
+
+fn main() {
+    // NOTE the router lookup time should stay flat as the number of routes grows,
+    // the linear scan grows with it
+    for n in [10, 100, 1000, 10000] {
+        let patterns = patterns(n);
+        let router = router(&patterns);
+        let paths = [
+            "/api/v1/res7/42/edit",
+            "/static/css/main.css",
+            "/missing/path",
+        ];
+
+        println!(
+            "{:>5} routes: router {:?} ---> linear {:?}",
+            n,
+            bench(&paths, |path| router.find(path).map(|(idx, _)| *idx)),
+            bench(&paths, |path| linear(&patterns, path)),
+        );
+    }
+}
+
+fn patterns(n: usize) -> Vec<Pattern> {
+    let mut patterns = (0..n)
+        .map(|i| format!("/api/v1/res{}/:id/edit", i).parse().unwrap())
+        .collect::<Vec<Pattern>>();
+    patterns.push("/static/**".parse().unwrap());
+
+    patterns
+}
+
+fn router(patterns: &[Pattern]) -> Router<usize> {
+    let mut router = Router::new();
+    for (idx, p) in patterns.iter().enumerate() {
+        router.insert(p.clone(), idx).unwrap();
+    }
+
+    router
+}
+
+// what looking a route up used to be, every pattern is tried
+fn linear(patterns: &[Pattern], path: &str) -> Option<usize> {
+    patterns
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| p.matches(path).is_some())
+        .min_by(|(_, a), (_, b)| a.precedence(b))
+        .map(|(idx, _)| idx)
+}
+
+fn bench(paths: &[&str], f: impl Fn(&str) -> Option<usize>) -> Duration {
+    const RUNS: u32 = 1000;
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        for path in paths {
+            std::hint::black_box(f(std::hint::black_box(path)));
+        }
+    }
+
+    Instant::now().duration_since(start) / RUNS
+}
@@ -3,14 +3,18 @@ mod interpreter;
 mod lex;
 mod origin_set;
 mod parse;
+mod pattern;
 mod query;
+mod router;
 mod url;
 
 pub use errors::{ParseError, ParseResult};
 pub use interpreter::{TransmuteError, origin::Origin, resource::Resource, route::Route};
 pub use origin_set::OriginSet;
 pub use parse::Parser;
+pub use pattern::{Params, Pattern, Segment};
 // Token needs to be public for the tests in `tests/lex.rs`
 pub use lex::{Token, lex};
 pub use query::Query;
+pub use router::Router;
 pub use url::{Scheme, Url};
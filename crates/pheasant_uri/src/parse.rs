@@ -98,6 +98,10 @@ impl PrologueParser {
             Some(Token::Slash) => Ok(Parser::Domain(DomainParser::scheme_relative(self))),
             // path starts from value s
             Some(Token::Seq(s)) => Ok(Parser::Path(PathParser::with_sep(self, s))),
+            // path starts with a separator char, e.g., '/:id' or '/.well-known'
+            Some(tok @ (Token::Colon | Token::Dot | Token::AddressSign | Token::Equality)) => Ok(
+                Parser::Path(PathParser::with_seq(self, tok.as_str().to_owned())),
+            ),
             // error unexpected token
             Some(_) => Err(ref_res(ParseError::url(0)).unwrap()),
             // 1 slash token stream means a root route (path absolute url)
@@ -477,6 +481,7 @@ impl PathParser {
                 Some(Token::Seq(seq)) => {
                     if trailing {
                         self.push(seq)?;
+                        trailing = false;
                     } else {
                         self.path.last_mut().map(|s| s.push_str(&seq));
                     }
@@ -494,6 +499,7 @@ impl PathParser {
 
                     if trailing {
                         self.push(token.as_str().to_owned())?;
+                        trailing = false;
                     } else {
                         self.path
                             .last_mut()
@@ -684,6 +690,7 @@ impl AbsoluteParser {
                 Some(Token::Seq(seq)) => {
                     if trailing {
                         self.push(seq)?;
+                        trailing = false;
                     } else {
                         self.path.last_mut().map(|s| s.push_str(&seq));
                     }
@@ -701,6 +708,7 @@ impl AbsoluteParser {
 
                     if trailing {
                         self.push(token.as_str().to_owned())?;
+                        trailing = false;
                     } else {
                         self.path
                             .last_mut()
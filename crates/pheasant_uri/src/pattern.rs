@@ -0,0 +1,276 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Route;
+
+/// a segment of a route pattern
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Segment {
+    /// matches a path segment that is exactly the same
+    Static(String),
+    /// matches any non empty path segment and captures it under its name,
+    /// written as `:name` or `{name}`
+    Param(String),
+    /// matches any non empty path segment without capturing it, written as `*`
+    Wildcard,
+    /// matches the rest of the path, however many segments it has, even none,
+    /// written as `**` or `{*name}` to also capture it under a name
+    ///
+    /// only valid as the last segment, it is a `Wildcard` anywhere else
+    CatchAll(Option<String>),
+}
+
+impl Segment {
+    fn parse(s: &str) -> Self {
+        match s {
+            "*" => return Self::Wildcard,
+            "**" => return Self::CatchAll(None),
+            _ => (),
+        }
+        if let Some(name) = s.strip_prefix("{*").and_then(|s| s.strip_suffix('}'))
+            && !name.is_empty()
+        {
+            return Self::CatchAll(Some(name.to_owned()));
+        }
+
+        let name = match s.strip_prefix(':') {
+            Some(name) => Some(name),
+            None => s.strip_prefix('{').and_then(|s| s.strip_suffix('}')),
+        };
+
+        match name {
+            Some(name) if !name.is_empty() => Self::Param(name.to_owned()),
+            _ => Self::Static(s.to_owned()),
+        }
+    }
+
+    // lower ranks take precedence when more than one pattern matches a path
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Static(_) => 0,
+            Self::Param(_) => 1,
+            Self::Wildcard => 2,
+            Self::CatchAll(_) => 3,
+        }
+    }
+
+    // checks if both segments match the same path segments
+    fn overlaps(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Static(a), Self::Static(b)) => a == b,
+            (Self::Param(_), Self::Param(_))
+            | (Self::Wildcard, Self::Wildcard)
+            | (Self::CatchAll(_), Self::CatchAll(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Static(s) => f.write_str(s),
+            Self::Param(name) => write!(f, ":{}", name),
+            Self::Wildcard => f.write_str("*"),
+            Self::CatchAll(None) => f.write_str("**"),
+            Self::CatchAll(Some(name)) => write!(f, "{{*{}}}", name),
+        }
+    }
+}
+
+/// a route template that request paths are matched against,
+/// e.g., "/users/:id/posts/{post}", "/docs/*/index" or "/static/**"
+///
+/// a route without parameters or wildcards only matches itself
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Pattern {
+    segments: Vec<Segment>,
+}
+
+impl FromStr for Pattern {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = path_segments(s).map(Segment::parse).collect::<Vec<_>>();
+        // the rest of the path can only be caught by the last segment
+        let len = segments.len();
+        for seg in &mut segments[..len - 1] {
+            if let Segment::CatchAll(_) = seg {
+                *seg = Segment::Wildcard;
+            }
+        }
+
+        Ok(Self { segments })
+    }
+}
+
+impl From<&Route> for Pattern {
+    fn from(route: &Route) -> Self {
+        route.as_str().parse().unwrap()
+    }
+}
+
+impl Pattern {
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// checks if the pattern has no parameters or wildcards, i.e., it only matches one path
+    pub fn is_static(&self) -> bool {
+        self.segments
+            .iter()
+            .all(|seg| matches!(seg, Segment::Static(_)))
+    }
+
+    /// the names of the pattern parameters, in order
+    pub fn param_names(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().filter_map(|seg| match seg {
+            Segment::Param(name) | Segment::CatchAll(Some(name)) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// matches the path against this pattern,
+    /// returns the captured parameters if it matches
+    ///
+    /// ```
+    /// # use pheasant_uri::Pattern;
+    /// let pattern = "/users/:id".parse::<Pattern>().unwrap();
+    /// let params = pattern.matches("/users/42").unwrap();
+    ///
+    /// assert_eq!(params.get("id"), Some("42"));
+    /// ```
+    pub fn matches(&self, path: &str) -> Option<Params> {
+        let mut params = Params::default();
+        let parts = path_segments(path).collect::<Vec<_>>();
+
+        for (idx, seg) in self.segments.iter().enumerate() {
+            if let Segment::CatchAll(name) = seg {
+                let rest = parts.get(idx..).unwrap_or_default().join("/");
+                if let Some(name) = name {
+                    params.push(name, &rest);
+                }
+                params.set_rest(rest);
+
+                return Some(params);
+            }
+
+            let part = *parts.get(idx)?;
+            match seg {
+                Segment::Static(s) if s == part => (),
+                Segment::Param(name) if !part.is_empty() => params.push(name, part),
+                Segment::Wildcard if !part.is_empty() => (),
+                _ => return None,
+            }
+        }
+
+        (parts.len() == self.segments.len()).then_some(params)
+    }
+
+    /// checks if both patterns match the same paths with the same precedence,
+    /// i.e., a request path can't tell them apart
+    ///
+    /// "/users/:id" and "/users/{name}" conflict,
+    /// "/users/:id" and "/users/me" don't, the static segment takes precedence
+    pub fn conflicts_with(&self, other: &Self) -> bool {
+        self.segments.len() == other.segments.len()
+            && self
+                .segments
+                .iter()
+                .zip(&other.segments)
+                .all(|(a, b)| a.overlaps(b))
+    }
+
+    /// orders patterns by precedence, the more specific pattern comes first
+    ///
+    /// segments are compared from left to right,
+    /// static segments beat parameters, which beat wildcards, which beat catch-alls
+    pub fn precedence(&self, other: &Self) -> Ordering {
+        self.segments
+            .iter()
+            .map(Segment::rank)
+            .cmp(other.segments.iter().map(Segment::rank))
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for seg in &self.segments {
+            write!(f, "/{}", seg)?;
+        }
+
+        Ok(())
+    }
+}
+
+// "/a/b" -> ["a", "b"], "/" -> [""]
+fn path_segments(path: &str) -> std::str::Split<'_, char> {
+    path.strip_prefix('/').unwrap_or(path).split('/')
+}
+
+/// the parameters a route pattern captured from a request path
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Params {
+    params: Vec<(String, String)>,
+    // the part of the path a catch-all segment matched
+    rest: Option<String>,
+}
+
+impl Params {
+    pub(crate) fn push(&mut self, name: &str, value: &str) {
+        self.params.push((name.to_owned(), value.to_owned()));
+    }
+
+    pub(crate) fn set_rest(&mut self, rest: String) {
+        self.rest = Some(rest);
+    }
+
+    /// returns the rest of the path a catch-all segment matched, named or not,
+    /// e.g., "css/main.css" for "/static/css/main.css" and the pattern "/static/**"
+    pub fn rest(&self) -> Option<&str> {
+        self.rest.as_deref()
+    }
+
+    /// returns the value captured under `name`
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// parses the value captured under `name` into `T`
+    ///
+    /// returns `None` if there is no such parameter or if it doesn't parse
+    ///
+    /// ```
+    /// # use pheasant_uri::{Params, Pattern};
+    /// # fn user(params: &Params) -> Option<u64> {
+    /// let id: u64 = params.parse("id")?;
+    /// # Some(id)
+    /// # }
+    /// # let pattern = "/users/:id".parse::<Pattern>().unwrap();
+    /// # assert_eq!(user(&pattern.matches("/users/42").unwrap()), Some(42));
+    /// ```
+    pub fn parse<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.get(name)?.parse().ok()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    /// iterates over the `(name, value)` pairs in pattern order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+}
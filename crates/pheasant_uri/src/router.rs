@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use crate::{Params, Pattern, Segment};
+
+/// a prefix tree of route patterns, keyed by path segments
+///
+/// looking a path up walks down the tree one segment at a time,
+/// so the cost depends on the path length and not on the number of patterns
+///
+/// when many patterns match a path, the one that comes first by `Pattern::precedence` wins
+///
+/// ```
+/// # use pheasant_uri::Router;
+/// let mut router = Router::new();
+/// router.insert("/users/:id".parse().unwrap(), "user").unwrap();
+/// router.insert("/users/me".parse().unwrap(), "me").unwrap();
+///
+/// let (value, params) = router.find("/users/42").unwrap();
+/// assert_eq!((*value, params.get("id")), ("user", Some("42")));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Router<T> {
+    root: Node<T>,
+    len: usize,
+}
+
+impl<T> Default for Router<T> {
+    fn default() -> Self {
+        Self {
+            root: Node::default(),
+            len: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    statics: HashMap<String, Node<T>>,
+    param: Option<Box<Node<T>>>,
+    wildcard: Option<Box<Node<T>>>,
+    // the pattern that ends with a catch-all segment right after this node
+    catch_all: Option<Leaf<T>>,
+    // the pattern that ends at this node
+    leaf: Option<Leaf<T>>,
+}
+
+impl<T> Default for Node<T> {
+    fn default() -> Self {
+        Self {
+            statics: HashMap::new(),
+            param: None,
+            wildcard: None,
+            catch_all: None,
+            leaf: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Leaf<T> {
+    pattern: Pattern,
+    value: T,
+}
+
+impl<T> Leaf<T> {
+    // names the captured values after the pattern parameters
+    fn params(&self, values: &[&str], rest: Option<String>) -> Params {
+        let mut params = Params::default();
+        for (name, value) in self.pattern.param_names().zip(values) {
+            params.push(name, value);
+        }
+        if let Some(rest) = rest {
+            if let Some(Segment::CatchAll(Some(name))) = self.pattern.segments().last() {
+                params.push(name, &rest);
+            }
+            params.set_rest(rest);
+        }
+
+        params
+    }
+}
+
+impl<T> Router<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds a pattern to the router
+    ///
+    /// ### Error
+    ///
+    /// returns the value of the already inserted pattern
+    /// if it conflicts with this one (see `Pattern::conflicts_with`)
+    pub fn insert(&mut self, pattern: Pattern, value: T) -> Result<(), &T> {
+        let node = self.root.slot(&pattern);
+        let leaf = match pattern.segments().last() {
+            Some(Segment::CatchAll(_)) => &mut node.catch_all,
+            _ => &mut node.leaf,
+        };
+
+        match leaf {
+            Some(leaf) => Err(&leaf.value),
+            None => {
+                *leaf = Some(Leaf { pattern, value });
+                self.len += 1;
+
+                Ok(())
+            }
+        }
+    }
+
+    /// returns the value of the inserted pattern that conflicts with `pattern`, if any
+    pub fn conflict(&self, pattern: &Pattern) -> Option<&T> {
+        let node = self.root.find_slot(pattern)?;
+        let leaf = match pattern.segments().last() {
+            Some(Segment::CatchAll(_)) => &node.catch_all,
+            _ => &node.leaf,
+        };
+
+        leaf.as_ref().map(|leaf| &leaf.value)
+    }
+
+    /// finds the pattern that matches the path,
+    /// returns its value along with the parameters it captured
+    pub fn find(&self, path: &str) -> Option<(&T, Params)> {
+        let parts = path
+            .strip_prefix('/')
+            .unwrap_or(path)
+            .split('/')
+            .collect::<Vec<_>>();
+        let mut values = vec![];
+
+        self.root.find(&parts, &mut values)
+    }
+
+    /// the number of patterns in the router
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<T> Node<T> {
+    // walks down to the node the pattern ends at, creating the missing nodes on the way
+    // a catch-all ends at the node of the segment before it
+    fn slot(&mut self, pattern: &Pattern) -> &mut Self {
+        let mut node = self;
+        for seg in pattern.segments() {
+            node = match seg {
+                Segment::Static(s) => node.statics.entry(s.clone()).or_default(),
+                Segment::Param(_) => node.param.get_or_insert_default(),
+                Segment::Wildcard => node.wildcard.get_or_insert_default(),
+                Segment::CatchAll(_) => break,
+            };
+        }
+
+        node
+    }
+
+    // same as `slot` without creating nodes
+    fn find_slot(&self, pattern: &Pattern) -> Option<&Self> {
+        let mut node = self;
+        for seg in pattern.segments() {
+            node = match seg {
+                Segment::Static(s) => node.statics.get(s)?,
+                Segment::Param(_) => node.param.as_ref()?,
+                Segment::Wildcard => node.wildcard.as_ref()?,
+                Segment::CatchAll(_) => break,
+            };
+        }
+
+        Some(node)
+    }
+
+    // depth first search in precedence order,
+    // static beats parameter beats wildcard beats catch-all at every level,
+    // so the first match found is the most specific one
+    fn find<'a>(&self, parts: &[&'a str], values: &mut Vec<&'a str>) -> Option<(&T, Params)> {
+        let Some((part, rest)) = parts.split_first() else {
+            if let Some(leaf) = &self.leaf {
+                return Some((&leaf.value, leaf.params(values, None)));
+            }
+
+            return self
+                .catch_all
+                .as_ref()
+                .map(|leaf| (&leaf.value, leaf.params(values, Some(String::new()))));
+        };
+
+        if let Some(found) = self
+            .statics
+            .get(*part)
+            .and_then(|node| node.find(rest, values))
+        {
+            return Some(found);
+        }
+
+        if !part.is_empty() {
+            if let Some(node) = &self.param {
+                values.push(part);
+                if let Some(found) = node.find(rest, values) {
+                    return Some(found);
+                }
+                values.pop();
+            }
+
+            if let Some(found) = self
+                .wildcard
+                .as_ref()
+                .and_then(|node| node.find(rest, values))
+            {
+                return Some(found);
+            }
+        }
+
+        self.catch_all
+            .as_ref()
+            .map(|leaf| (&leaf.value, leaf.params(values, Some(parts.join("/")))))
+    }
+}
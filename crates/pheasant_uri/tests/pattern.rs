@@ -0,0 +1,150 @@
+use std::cmp::Ordering;
+
+use pheasant_uri::{Pattern, Route, Segment};
+
+fn pattern(s: &str) -> Pattern {
+    s.parse().unwrap()
+}
+
+#[test]
+fn segments() {
+    assert_eq!(
+        pattern("/users/:id/posts/{post}").segments(),
+        &[
+            Segment::Static("users".into()),
+            Segment::Param("id".into()),
+            Segment::Static("posts".into()),
+            Segment::Param("post".into()),
+        ]
+    );
+    // a lone colon or empty braces don't name a parameter
+    assert!(pattern("/a/:/{}").is_static());
+    assert_eq!(pattern("/users/{id}").to_string(), "/users/:id");
+}
+
+#[test]
+fn static_match() {
+    let p = pattern("/about");
+
+    assert!(p.matches("/about").unwrap().is_empty());
+    assert!(p.matches("/about/").is_none());
+    assert!(p.matches("/abou").is_none());
+    assert!(pattern("/").matches("/").is_some());
+    assert!(pattern("/").matches("/a").is_none());
+}
+
+#[test]
+fn captures() {
+    let p = pattern("/users/:id/posts/:post_id");
+    let params = p.matches("/users/42/posts/hello").unwrap();
+
+    assert_eq!(params.get("id"), Some("42"));
+    assert_eq!(params.parse::<u32>("id"), Some(42));
+    assert_eq!(params.get("post_id"), Some("hello"));
+    assert_eq!(params.parse::<u32>("post_id"), None);
+    assert_eq!(params.get("missing"), None);
+    assert_eq!(
+        params.iter().collect::<Vec<_>>(),
+        [("id", "42"), ("post_id", "hello")]
+    );
+
+    assert!(p.matches("/users/42/posts").is_none());
+    assert!(p.matches("/users/42/posts/hello/more").is_none());
+    // parameters don't match empty segments
+    assert!(p.matches("/users//posts/hello").is_none());
+}
+
+#[test]
+fn conflicts() {
+    assert!(pattern("/users/:id").conflicts_with(&pattern("/users/{name}")));
+    assert!(pattern("/users").conflicts_with(&pattern("/users")));
+    assert!(!pattern("/users/:id").conflicts_with(&pattern("/users/me")));
+    assert!(!pattern("/users/:id").conflicts_with(&pattern("/users/:id/posts")));
+    assert!(!pattern("/:a/b").conflicts_with(&pattern("/a/:b")));
+}
+
+#[test]
+fn precedence() {
+    let (param, fixed) = (pattern("/users/:id"), pattern("/users/me"));
+    assert_eq!(fixed.precedence(&param), Ordering::Less);
+    assert_eq!(param.precedence(&fixed), Ordering::Greater);
+
+    // the leftmost differing segment decides
+    assert_eq!(
+        pattern("/a/:b").precedence(&pattern("/:a/b")),
+        Ordering::Less
+    );
+}
+
+#[test]
+fn wildcard() {
+    let p = pattern("/docs/*/index");
+
+    assert!(p.matches("/docs/v1/index").unwrap().is_empty());
+    assert!(p.matches("/docs//index").is_none());
+    assert!(p.matches("/docs/v1/v2/index").is_none());
+}
+
+#[test]
+fn catch_all() {
+    let p = pattern("/static/**");
+    assert_eq!(
+        p.matches("/static/css/main.css").unwrap().rest(),
+        Some("css/main.css")
+    );
+    assert_eq!(p.matches("/static").unwrap().rest(), Some(""));
+    assert!(p.matches("/statics/a").is_none());
+
+    let p = pattern("/files/{*path}");
+    let params = p.matches("/files/a/b/c").unwrap();
+    assert_eq!(params.get("path"), Some("a/b/c"));
+    assert_eq!(params.rest(), Some("a/b/c"));
+    assert_eq!(p.param_names().collect::<Vec<_>>(), ["path"]);
+
+    // only the last segment catches the rest of the path
+    assert_eq!(pattern("/a/**/b").segments()[1], Segment::Wildcard);
+}
+
+#[test]
+fn wildcard_conflicts() {
+    assert!(pattern("/static/**").conflicts_with(&pattern("/static/{*path}")));
+    assert!(pattern("/docs/*/index").conflicts_with(&pattern("/docs/*/index")));
+    assert!(!pattern("/docs/*").conflicts_with(&pattern("/docs/:page")));
+    assert!(!pattern("/docs/*").conflicts_with(&pattern("/docs/**")));
+}
+
+#[test]
+fn wildcard_precedence() {
+    let mut patterns = [
+        pattern("/static/**"),
+        pattern("/static/*"),
+        pattern("/static/:file"),
+        pattern("/static/index.html"),
+    ];
+    patterns.sort_by(Pattern::precedence);
+
+    assert_eq!(
+        patterns.iter().map(Pattern::to_string).collect::<Vec<_>>(),
+        [
+            "/static/index.html",
+            "/static/:file",
+            "/static/*",
+            "/static/**"
+        ]
+    );
+    // a shorter static route beats a catch-all that also matches it
+    assert_eq!(
+        pattern("/static").precedence(&pattern("/static/**")),
+        Ordering::Less
+    );
+}
+
+#[test]
+fn from_route() {
+    // parameters survive the route parser, even right after the root slash
+    for route in ["/:page", "/users/:id/posts/:post_id", "/static/**"] {
+        let route = Route::macro_checked(route);
+
+        assert_eq!(Pattern::from(&route).to_string(), route.as_str());
+    }
+}
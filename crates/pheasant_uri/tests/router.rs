@@ -0,0 +1,123 @@
+use pheasant_uri::{Pattern, Router};
+
+fn router(patterns: &[&'static str]) -> Router<&'static str> {
+    let mut router = Router::new();
+    for p in patterns {
+        router.insert(p.parse().unwrap(), *p).unwrap();
+    }
+
+    router
+}
+
+fn found(router: &Router<&'static str>, path: &str) -> Option<&'static str> {
+    router.find(path).map(|(value, _)| *value)
+}
+
+#[test]
+fn static_routes() {
+    let r = router(&["/", "/about", "/about/team"]);
+
+    assert_eq!(found(&r, "/"), Some("/"));
+    assert_eq!(found(&r, "/about"), Some("/about"));
+    assert_eq!(found(&r, "/about/team"), Some("/about/team"));
+    assert_eq!(found(&r, "/about/"), None);
+    assert_eq!(found(&r, "/team"), None);
+    assert_eq!(r.len(), 3);
+}
+
+#[test]
+fn params() {
+    let r = router(&["/users/:id", "/users/:id/posts/:post", "/users/me"]);
+
+    let (value, params) = r.find("/users/42/posts/7").unwrap();
+    assert_eq!(*value, "/users/:id/posts/:post");
+    assert_eq!(params.get("id"), Some("42"));
+    assert_eq!(params.get("post"), Some("7"));
+
+    assert_eq!(found(&r, "/users/me"), Some("/users/me"));
+    assert_eq!(found(&r, "/users/you"), Some("/users/:id"));
+    assert_eq!(found(&r, "/users/"), None);
+}
+
+#[test]
+fn backtracking() {
+    // the static branch matches the first segment but not the rest of the path
+    let r = router(&["/users/me/settings", "/users/:id/posts"]);
+
+    let (value, params) = r.find("/users/me/posts").unwrap();
+    assert_eq!(*value, "/users/:id/posts");
+    assert_eq!(params.get("id"), Some("me"));
+}
+
+#[test]
+fn wildcards() {
+    let r = router(&[
+        "/static/**",
+        "/static/*/index",
+        "/static/:dir/about",
+        "/static/index.html",
+        "/files/{*path}",
+    ]);
+
+    assert_eq!(found(&r, "/static/index.html"), Some("/static/index.html"));
+    assert_eq!(found(&r, "/static/v1/about"), Some("/static/:dir/about"));
+    assert_eq!(found(&r, "/static/v1/index"), Some("/static/*/index"));
+    assert_eq!(found(&r, "/static/css/main.css"), Some("/static/**"));
+    assert_eq!(found(&r, "/static"), Some("/static/**"));
+
+    let (_, params) = r.find("/static/v1/about/more").unwrap();
+    assert_eq!(params.rest(), Some("v1/about/more"));
+    assert_eq!(params.get("dir"), None);
+
+    let (_, params) = r.find("/files/a/b").unwrap();
+    assert_eq!(params.get("path"), Some("a/b"));
+}
+
+#[test]
+fn conflicts() {
+    let mut r = router(&["/users/:id", "/static/**"]);
+
+    assert_eq!(
+        r.insert("/users/{name}".parse().unwrap(), "dup"),
+        Err(&"/users/:id")
+    );
+    assert_eq!(
+        r.conflict(&"/static/{*path}".parse().unwrap()),
+        Some(&"/static/**")
+    );
+    assert_eq!(r.conflict(&"/users/me".parse().unwrap()), None);
+    assert_eq!(r.len(), 2);
+}
+
+#[test]
+fn same_as_patterns() {
+    // the router picks the same pattern as sorting the matching patterns by precedence
+    let patterns = [
+        "/",
+        "/**",
+        "/a/:b",
+        "/a/*",
+        "/a/b",
+        "/:a/b",
+        "/:a/:b/c",
+        "/*/b/**",
+        "/a/b/{*rest}",
+    ];
+    let r = router(&patterns);
+    let parsed = patterns
+        .iter()
+        .map(|p| (p.parse::<Pattern>().unwrap(), *p))
+        .collect::<Vec<_>>();
+
+    for path in [
+        "/", "/a", "/a/b", "/a/c", "/x/b", "/a/b/c", "/x/y/c", "/x/b/y", "/a/b/c/d",
+    ] {
+        let expected = parsed
+            .iter()
+            .filter(|(p, _)| p.matches(path).is_some())
+            .min_by(|(a, _), (b, _)| a.precedence(b))
+            .map(|(_, p)| *p);
+
+        assert_eq!(found(&r, path), expected, "{}", path);
+    }
+}
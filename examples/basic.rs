@@ -1,12 +1,18 @@
 use chrono::{DateTime, Utc};
 use pheasant::{
-    Cookie, HeaderMap, Method, Mime, Protocol, Request, Response, Server, Service, fail, get,
+    Cookie, Cors, HeaderMap, Method, Mime, OriginSet, Protocol, Request, Response, Server, Service,
+    fail, get,
 };
 
 #[tokio::main]
 async fn main() {
     let mut phe = Server::new([127, 0, 0, 1], 8883, 3333).unwrap();
     phe.service(hello).service(favicon).error(not_found);
+    // answers `OPTIONS *` and the preflights of the routes without an options service
+    let mut cors = Cors::new();
+    cors.methods().extend([Method::Get, Method::Options]);
+    cors.overwrite_origins(OriginSet::AnyOrigin);
+    phe.cors(cors);
     // .service(|| Service::new(Method::Get, "/icon", [], "image/svg+xml", svg));
 
     phe.serve().await;
@@ -73,12 +79,3 @@ async fn not_found() -> Response {
 
     resp
 }
-
-// TODO #[status(200)] macro attr
-// server wide preflight req resp
-// #[options("*")]
-// async fn server_options(_: ()) -> Response {
-//     let mut resp = Response::default();
-//
-//     resp
-// }
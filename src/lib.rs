@@ -1,11 +1,14 @@
 // lib exports
 pub use pheasant_core::{
-    ClientError, Cookie, Cors, ErrorStatus, Failure, Header, HeaderMap, Informational, Method,
-    Mime, Protocol, Redirection, Request, Response, Server, ServerError, Service, ServiceBundle,
-    Status, Successful,
+    Body, BodyStream, ClientCert, ClientError, Cookie, Cors, ErrorStatus, Event, Failure, Header,
+    HeaderMap, Informational, Io, KeepAlive, Load, Message, Method, Middleware, Mime, Protocol,
+    Redirection, RegisterError, Request, Response, Scope, Server, ServerError, Service,
+    ServiceBundle, Shutdown, Sse, Status, Successful, TlsError, TlsInfo, Upgrade, WebSocket,
+    WsReceiver, WsSender, WsUpgrade, signals,
 };
+pub use pheasant_core::{middleware, tls};
 pub use pheasant_macro_utils::RequestOrigin;
-pub use pheasant_uri::{Origin, OriginSet, Resource, Route, Url};
+pub use pheasant_uri::{Origin, OriginSet, Params, Pattern, Resource, Route, Url};
 
 // macro exports
 pub use pheasant_macro_fail::fail;